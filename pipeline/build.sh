@@ -1,3 +1,6 @@
+# Validate every shader before spending time on the wasm build
+cargo run --bin pipeline -- --check-shaders || exit 1
+
 RUSTFLAGS=--cfg=web_sys_unstable_apis cargo build --no-default-features --target wasm32-unknown-unknown \
 --bin pipeline
 
@@ -0,0 +1,21 @@
+// 高斯模糊：沿 params.xy 方向采样 9 次，横竖各做一遍
+@group(0) @binding(0)
+var input_sampler: sampler;
+@group(0) @binding(1)
+var input: texture_2d<f32>;
+// xy: 以像素为单位的方向
+@group(0) @binding(2)
+var<uniform> params: vec4f;
+
+@fragment
+fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4f {
+  var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+  let texel = params.xy / vec2f(textureDimensions(input));
+  var color = textureSample(input, input_sampler, uv) * weights[0];
+  for (var i = 1; i < 5; i++) {
+    let offset = texel * f32(i);
+    color += textureSample(input, input_sampler, uv + offset) * weights[i];
+    color += textureSample(input, input_sampler, uv - offset) * weights[i];
+  }
+  return color;
+}
@@ -0,0 +1,347 @@
+use std::time::Duration;
+
+use glam::{Mat4, Vec3};
+use winit::{
+  event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+  keyboard::{KeyCode, PhysicalKey},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+  // `fovy` is the vertical field of view in radians
+  Perspective { fovy: f32, znear: f32, zfar: f32 },
+  // `height` is the visible height in world units, the width follows the aspect ratio
+  Orthographic { height: f32, znear: f32, zfar: f32 },
+}
+
+impl Projection {
+  pub fn perspective() -> Self {
+    Projection::Perspective { fovy: 45f32.to_radians(), znear: 0.1, zfar: 100.0 }
+  }
+
+  pub fn orthographic() -> Self {
+    Projection::Orthographic { height: 2.0, znear: 0.1, zfar: 100.0 }
+  }
+
+  // Right handed, mapping depth to wgpu's [0, 1] range.
+  pub fn matrix(&self, aspect: f32) -> Mat4 {
+    match *self {
+      Projection::Perspective { fovy, znear, zfar } => Mat4::perspective_rh(fovy, aspect, znear, zfar),
+      Projection::Orthographic { height, znear, zfar } => {
+        let (half_width, half_height) = (height * aspect * 0.5, height * 0.5);
+        Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, znear, zfar)
+      }
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+  pub eye: Vec3,
+  pub target: Vec3,
+  pub up: Vec3,
+  // width / height of the render target
+  pub aspect: f32,
+  pub projection: Projection,
+}
+
+impl Default for Camera {
+  // Looks down -z at the origin from where the z = 0 plane's [-1, 1] square fills the
+  // view, so geometry authored in clip space shows up the same size either way.
+  fn default() -> Self {
+    Self::new(Projection::perspective())
+  }
+}
+
+impl Camera {
+  pub fn new(projection: Projection) -> Self {
+    let distance = match projection {
+      Projection::Perspective { fovy, .. } => 1.0 / (fovy * 0.5).tan(),
+      Projection::Orthographic { .. } => 2.0,
+    };
+    Self {
+      eye: Vec3::new(0.0, 0.0, distance),
+      target: Vec3::ZERO,
+      up: Vec3::Y,
+      aspect: 1.0,
+      projection,
+    }
+  }
+
+  pub fn view_matrix(&self) -> Mat4 {
+    Mat4::look_at_rh(self.eye, self.target, self.up)
+  }
+
+  pub fn projection_matrix(&self) -> Mat4 {
+    self.projection.matrix(self.aspect)
+  }
+
+  pub fn view_proj(&self) -> Mat4 {
+    self.projection_matrix() * self.view_matrix()
+  }
+}
+
+// The `@group(0) @binding(0)` uniform of the pipeline shaders.
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct CameraUniform {
+  pub view_proj: [[f32; 4]; 4],
+}
+
+impl CameraUniform {
+  // Passes positions through as clip space coordinates.
+  pub fn identity() -> Self {
+    Self { view_proj: Mat4::IDENTITY.to_cols_array_2d() }
+  }
+
+  pub fn from_camera(camera: &Camera) -> Self {
+    Self { view_proj: camera.view_proj().to_cols_array_2d() }
+  }
+}
+
+// Uniform buffer and bind group for `CameraUniform`.
+pub(crate) struct CameraBinding {
+  buffer: wgpu::Buffer,
+  pub bind_group_layout: wgpu::BindGroupLayout,
+  pub bind_group: wgpu::BindGroup,
+}
+
+impl CameraBinding {
+  pub fn new(device: &wgpu::Device, uniform: &CameraUniform) -> Self {
+    use wgpu::util::DeviceExt;
+
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Camera Buffer"),
+      contents: bytemuck::bytes_of(uniform),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some("Camera Bind Group Layout"),
+      entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CameraUniform>() as u64),
+        },
+        count: None,
+      }],
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Camera Bind Group"),
+      layout: &bind_group_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+
+    Self { buffer, bind_group_layout, bind_group }
+  }
+
+  pub fn write(&self, queue: &wgpu::Queue, uniform: &CameraUniform) {
+    queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(uniform));
+  }
+}
+
+// Left drag rotates around the target, the wheel zooms.
+#[derive(Clone, Debug)]
+pub struct OrbitController {
+  pub yaw: f32,
+  pub pitch: f32,
+  pub distance: f32,
+  // radians per pixel of mouse movement
+  pub rotate_speed: f32,
+  // the visible height of an orthographic camera per unit of distance, moving closer
+  // doesn't change what it sees so zooming scales this instead
+  ortho_height: f32,
+  dragging: bool,
+  last_cursor: Option<(f64, f64)>,
+}
+
+impl OrbitController {
+  // Starts from wherever `camera` currently is.
+  pub fn new(camera: &Camera) -> Self {
+    let offset = camera.eye - camera.target;
+    let distance = offset.length().max(f32::EPSILON);
+    let ortho_height = match camera.projection {
+      Projection::Orthographic { height, .. } => height / distance,
+      // what `Camera::new` sets up
+      Projection::Perspective { .. } => 1.0,
+    };
+    Self {
+      yaw: offset.x.atan2(offset.z),
+      pitch: (offset.y / distance).clamp(-1.0, 1.0).asin(),
+      distance,
+      rotate_speed: 0.005,
+      ortho_height,
+      dragging: false,
+      last_cursor: None,
+    }
+  }
+
+  // Returns whether the event was used.
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+        self.dragging = *state == ElementState::Pressed;
+        true
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let cursor = (position.x, position.y);
+        if let (true, Some(last)) = (self.dragging, self.last_cursor) {
+          self.rotate((cursor.0 - last.0) as f32, (cursor.1 - last.1) as f32);
+        }
+        self.last_cursor = Some(cursor);
+        self.dragging
+      }
+      WindowEvent::MouseWheel { delta, .. } => {
+        self.zoom(scroll_lines(delta));
+        true
+      }
+      _ => false,
+    }
+  }
+
+  pub fn rotate(&mut self, dx: f32, dy: f32) {
+    let limit = std::f32::consts::FRAC_PI_2 - 0.01;
+    self.yaw -= dx * self.rotate_speed;
+    self.pitch = (self.pitch + dy * self.rotate_speed).clamp(-limit, limit);
+  }
+
+  // Positive `lines` move towards the target, or narrow an orthographic view.
+  pub fn zoom(&mut self, lines: f32) {
+    self.distance = (self.distance * 0.9f32.powf(lines)).max(0.01);
+  }
+
+  pub fn update_camera(&self, camera: &mut Camera) {
+    let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+    let offset = Vec3::new(sin_yaw * cos_pitch, sin_pitch, cos_yaw * cos_pitch) * self.distance;
+    camera.eye = camera.target + offset;
+    camera.up = Vec3::Y;
+    if let Projection::Orthographic { height, .. } = &mut camera.projection {
+      *height = self.ortho_height * self.distance;
+    }
+  }
+}
+
+// WASD moves, Space / Shift go up and down, left drag looks around.
+#[derive(Clone, Debug)]
+pub struct FlyController {
+  pub yaw: f32,
+  pub pitch: f32,
+  // world units per second
+  pub move_speed: f32,
+  pub look_speed: f32,
+  forward: f32,
+  right: f32,
+  up: f32,
+  pressed: [bool; 6],
+  dragging: bool,
+  last_cursor: Option<(f64, f64)>,
+}
+
+const FLY_KEYS: [KeyCode; 6] = [KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyA, KeyCode::Space, KeyCode::ShiftLeft];
+
+impl FlyController {
+  pub fn new(camera: &Camera) -> Self {
+    let direction = (camera.target - camera.eye).normalize_or_zero();
+    Self {
+      yaw: direction.x.atan2(-direction.z),
+      pitch: direction.y.clamp(-1.0, 1.0).asin(),
+      move_speed: 1.2,
+      look_speed: 0.005,
+      forward: 0.0,
+      right: 0.0,
+      up: 0.0,
+      pressed: [false; 6],
+      dragging: false,
+      last_cursor: None,
+    }
+  }
+
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::KeyboardInput {
+        event: KeyEvent { physical_key: PhysicalKey::Code(code), state, .. },
+        ..
+      } => {
+        let Some(i) = FLY_KEYS.iter().position(|key| key == code) else { return false };
+        self.pressed[i] = *state == ElementState::Pressed;
+        let axis = |positive: usize, negative: usize| self.pressed[positive] as i32 as f32 - self.pressed[negative] as i32 as f32;
+        (self.forward, self.right, self.up) = (axis(0, 1), axis(2, 3), axis(4, 5));
+        true
+      }
+      WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+        self.dragging = *state == ElementState::Pressed;
+        true
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let cursor = (position.x, position.y);
+        if let (true, Some(last)) = (self.dragging, self.last_cursor) {
+          self.look((cursor.0 - last.0) as f32, (cursor.1 - last.1) as f32);
+        }
+        self.last_cursor = Some(cursor);
+        self.dragging
+      }
+      _ => false,
+    }
+  }
+
+  pub fn look(&mut self, dx: f32, dy: f32) {
+    let limit = std::f32::consts::FRAC_PI_2 - 0.01;
+    self.yaw += dx * self.look_speed;
+    self.pitch = (self.pitch - dy * self.look_speed).clamp(-limit, limit);
+  }
+
+  fn direction(&self) -> Vec3 {
+    let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+    Vec3::new(sin_yaw * cos_pitch, sin_pitch, -cos_yaw * cos_pitch)
+  }
+
+  // Moves as far as whatever keys are held go in `dt`, the time since the last update.
+  pub fn update_camera(&self, camera: &mut Camera, dt: Duration) {
+    let forward = self.direction();
+    let right = forward.cross(Vec3::Y).normalize_or_zero();
+    let velocity = forward * self.forward + right * self.right + Vec3::Y * self.up;
+    camera.eye += velocity * self.move_speed * dt.as_secs_f32();
+    camera.target = camera.eye + forward;
+    camera.up = Vec3::Y;
+  }
+}
+
+#[derive(Clone, Debug)]
+pub enum CameraController {
+  Orbit(OrbitController),
+  Fly(FlyController),
+}
+
+impl CameraController {
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match self {
+      CameraController::Orbit(controller) => controller.process_event(event),
+      CameraController::Fly(controller) => controller.process_event(event),
+    }
+  }
+
+  // `dt` is the time since the last update.
+  pub fn update_camera(&self, camera: &mut Camera, dt: Duration) {
+    match self {
+      CameraController::Orbit(controller) => controller.update_camera(camera),
+      CameraController::Fly(controller) => controller.update_camera(camera, dt),
+    }
+  }
+}
+
+fn scroll_lines(delta: &MouseScrollDelta) -> f32 {
+  match delta {
+    MouseScrollDelta::LineDelta(_, y) => *y,
+    // roughly one line per 20 pixels of touchpad scrolling
+    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 20.0,
+  }
+}
@@ -0,0 +1,208 @@
+use std::{fmt, marker::PhantomData};
+
+use crate::reflect;
+
+#[derive(Debug)]
+pub enum ComputeError {
+  // no adapter at all, e.g. on a CI machine without a GPU or a software rasterizer
+  NoAdapter,
+  // WebGL2 and some downlevel adapters have no compute shaders or storage buffers
+  Unsupported(wgpu::AdapterInfo),
+  RequestDevice(wgpu::RequestDeviceError),
+  Map(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for ComputeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ComputeError::NoAdapter => write!(f, "no adapter available for headless compute"),
+      ComputeError::Unsupported(info) => write!(f, "{} ({:?}) can't run compute shaders", info.name, info.backend),
+      ComputeError::RequestDevice(e) => write!(f, "failed to request a compute device: {e}"),
+      ComputeError::Map(e) => write!(f, "failed to map readback buffer: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for ComputeError {}
+
+// Whether `device` can run the kernels in this crate, which need two storage buffers per stage.
+pub fn supports_compute(adapter: &wgpu::Adapter, device: &wgpu::Device) -> bool {
+  adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+    && device.limits().max_storage_buffers_per_shader_stage >= 2
+    && device.limits().max_compute_invocations_per_workgroup > 0
+}
+
+// Workgroups needed to cover `count` invocations, one invocation per item.
+pub fn dispatch_size(count: u32, workgroup_size: u32) -> u32 {
+  count.div_ceil(workgroup_size)
+}
+
+// A storage buffer holding `len` values of `T`. `usage` is added to STORAGE and COPY_DST,
+// e.g. VERTEX to draw from what a kernel wrote.
+pub struct StorageBuffer<T> {
+  buffer: wgpu::Buffer,
+  len: usize,
+  _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> StorageBuffer<T> {
+  pub fn new(device: &wgpu::Device, label: &str, len: usize, usage: wgpu::BufferUsages) -> Self {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some(label),
+      // zero sized bindings aren't allowed
+      size: (len.max(1) * std::mem::size_of::<T>()) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC | usage,
+      mapped_at_creation: false,
+    });
+    Self { buffer, len, _marker: PhantomData }
+  }
+
+  pub fn with_data(device: &wgpu::Device, queue: &wgpu::Queue, label: &str, data: &[T], usage: wgpu::BufferUsages) -> Self {
+    let buffer = Self::new(device, label, data.len(), usage);
+    buffer.write(queue, data);
+    buffer
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    &self.buffer
+  }
+
+  pub fn binding(&self) -> wgpu::BindingResource<'_> {
+    self.buffer.as_entire_binding()
+  }
+
+  // Overwrites the start of the buffer, `data` may not be longer than `len`.
+  pub fn write(&self, queue: &wgpu::Queue, data: &[T]) {
+    assert!(data.len() <= self.len, "{} values don't fit in a storage buffer of {}", data.len(), self.len);
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(data));
+  }
+
+  // Copies the buffer back after all submitted work is done. Blocks, so native only.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<T>, ComputeError> {
+    let size = (self.len * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+    if size == 0 {
+      return Ok(Vec::new());
+    }
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Storage Readback Buffer"),
+      size,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("Storage Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(&self.buffer, 0, &readback, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = readback.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+      let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap().map_err(ComputeError::Map)?;
+
+    let data = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+    readback.unmap();
+    Ok(data)
+  }
+}
+
+// A compute pipeline for one WGSL entry point. The bind group layout is derived from the
+// shader and the workgroup size is read from its `@workgroup_size`.
+pub struct ComputeKernel {
+  pipeline: wgpu::ComputePipeline,
+  workgroup_size: [u32; 3],
+}
+
+impl ComputeKernel {
+  pub fn new(device: &wgpu::Device, label: &str, source: &str, entry_point: &str) -> Self {
+    let workgroup_size = reflect::parse_wgsl(source)
+      .map_err(|e| e.to_string())
+      .and_then(|module| {
+        module.entry_points.iter()
+          .find(|entry| entry.name == entry_point && entry.stage == naga::ShaderStage::Compute)
+          .map(|entry| entry.workgroup_size)
+          .ok_or_else(|| format!("no compute entry point named `{entry_point}`"))
+      })
+      .unwrap_or_else(|e| panic!("[compute]: {label}: {e}"));
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some(label),
+      source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+      label: Some(&format!("{label} Compute Pipeline")),
+      layout: None,
+      module: &module,
+      entry_point,
+    });
+
+    Self { pipeline, workgroup_size }
+  }
+
+  pub fn workgroup_size(&self) -> [u32; 3] {
+    self.workgroup_size
+  }
+
+  pub fn bind_group_layout(&self, index: u32) -> wgpu::BindGroupLayout {
+    self.pipeline.get_bind_group_layout(index)
+  }
+
+  // Runs one invocation per item for `count` items, laid out along x.
+  pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, count: u32) {
+    let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+      label: Some("Compute Pass"),
+      timestamp_writes: None,
+    });
+    compute_pass.set_pipeline(&self.pipeline);
+    compute_pass.set_bind_group(0, bind_group, &[]);
+    compute_pass.dispatch_workgroups(dispatch_size(count, self.workgroup_size[0]), 1, 1);
+  }
+}
+
+// A device for running kernels without a window, on the software adapter when there is one.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct HeadlessCompute {
+  pub device: wgpu::Device,
+  pub queue: wgpu::Queue,
+  pub adapter_info: wgpu::AdapterInfo,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HeadlessCompute {
+  pub async fn new() -> Result<Self, ComputeError> {
+    let adapter = renderer::request_fallback_adapter().await.ok_or(ComputeError::NoAdapter)?;
+    let adapter_info = adapter.get_info();
+    log::info!("[compute]: adapter_info {:?}", adapter_info);
+
+    if !adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS) {
+      return Err(ComputeError::Unsupported(adapter_info));
+    }
+
+    let (device, queue) = adapter.request_device(
+      &wgpu::DeviceDescriptor {
+        label: Some("Headless Compute Device"),
+        required_features: wgpu::Features::empty(),
+        required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+      },
+      None,
+    ).await.map_err(ComputeError::RequestDevice)?;
+
+    if !supports_compute(&adapter, &device) {
+      return Err(ComputeError::Unsupported(adapter_info));
+    }
+    Ok(Self { device, queue, adapter_info })
+  }
+}
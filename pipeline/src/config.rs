@@ -0,0 +1,207 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShaderLang {
+  #[default]
+  Wgsl,
+  Glsl,
+}
+
+impl FromStr for ShaderLang {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "wgsl" => Ok(ShaderLang::Wgsl),
+      "glsl" => Ok(ShaderLang::Glsl),
+      _ => Err(format!("unknown shader language `{s}`, expected wgsl or glsl")),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+  #[default]
+  Orbit,
+  Fly,
+}
+
+impl FromStr for CameraMode {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "orbit" => Ok(CameraMode::Orbit),
+      "fly" => Ok(CameraMode::Fly),
+      _ => Err(format!("unknown camera `{s}`, expected orbit or fly")),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthFormat {
+  #[default]
+  Depth32Float,
+  Depth24PlusStencil8,
+}
+
+impl DepthFormat {
+  pub fn texture_format(self) -> wgpu::TextureFormat {
+    match self {
+      DepthFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+      DepthFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+    }
+  }
+}
+
+// A post-processing effect applied to the scene, see `RenderGraph::post_process`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+  Grayscale,
+  Blur,
+  Tonemap,
+}
+
+impl FromStr for Effect {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "grayscale" => Ok(Effect::Grayscale),
+      "blur" => Ok(Effect::Blur),
+      "tonemap" => Ok(Effect::Tonemap),
+      _ => Err(format!("unknown effect `{s}`, expected grayscale, blur or tonemap")),
+    }
+  }
+}
+
+impl fmt::Display for Effect {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Effect::Grayscale => write!(f, "grayscale"),
+      Effect::Blur => write!(f, "blur"),
+      Effect::Tonemap => write!(f, "tonemap"),
+    }
+  }
+}
+
+// `none` turns the depth buffer off.
+fn parse_depth(s: &str) -> Result<Option<DepthFormat>, String> {
+  match s.to_ascii_lowercase().as_str() {
+    "none" => Ok(None),
+    "depth32float" => Ok(Some(DepthFormat::Depth32Float)),
+    "depth24plusstencil8" => Ok(Some(DepthFormat::Depth24PlusStencil8)),
+    _ => Err(format!("unknown depth format `{s}`, expected depth32float, depth24plusstencil8 or none")),
+  }
+}
+
+// Startup options for `run`, e.g. `pipeline --shader glsl --model cube.obj --camera fly`.
+#[derive(Clone, Debug)]
+pub struct Config {
+  pub shader: ShaderLang,
+  // rebuild the pipeline when the shader files under `src/` change, native only
+  pub hot_reload: bool,
+  // .obj, .gltf or .glb file drawn instead of the triangle, native only
+  pub model: Option<PathBuf>,
+  // PNG or JPEG drawn on a quad instead of the colored mesh, native only
+  pub texture: Option<PathBuf>,
+  // draw this many copies of the mesh on a grid in one instanced call
+  pub instances: u32,
+  // simulate this many particles with a compute shader and draw them as instances,
+  // skipped on adapters without compute support
+  pub particles: u32,
+  pub camera: CameraMode,
+  // orthographic instead of perspective projection
+  pub ortho: bool,
+  // depth/stencil target format, `None` draws in submission order
+  pub depth: Option<DepthFormat>,
+  // MSAA samples per pixel: 1, 2, 4 or 8, changed to the nearest count the device supports
+  pub sample_count: u32,
+  // effects applied in order after the scene pass, e.g. `--post grayscale,blur`
+  pub post: Vec<Effect>,
+  // draw at this multiple of the window size, e.g. 2 to supersample, see `renderer::SurfaceSize`
+  pub render_scale: f32,
+  // falls back to a mode the surface supports, e.g. `--present-mode mailbox`
+  pub present_mode: wgpu::PresentMode,
+  // frames queued ahead of the display
+  pub frame_latency: u32,
+  // wait between frames instead of drawing as fast as the present mode allows
+  pub max_fps: Option<f64>,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      shader: ShaderLang::default(),
+      hot_reload: false,
+      model: None,
+      texture: None,
+      instances: 0,
+      particles: 0,
+      camera: CameraMode::default(),
+      ortho: false,
+      depth: Some(DepthFormat::default()),
+      sample_count: 1,
+      post: Vec::new(),
+      render_scale: 1.0,
+      present_mode: wgpu::PresentMode::Fifo,
+      frame_latency: 2,
+      max_fps: None,
+    }
+  }
+}
+
+impl Config {
+  pub fn from_args() -> Result<Self, String> {
+    Self::parse(std::env::args().skip(1))
+  }
+
+  // Flags take their value as the next argument or after `=`, e.g. `--msaa 4` or `--msaa=4`.
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+    let mut config = Self::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      let (flag, inline) = match arg.split_once('=') {
+        Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+        None => (arg.clone(), None),
+      };
+      let mut value = || inline.clone().or_else(|| args.next()).ok_or_else(|| format!("{flag} needs a value"));
+
+      match flag.as_str() {
+        "--shader" => config.shader = value()?.parse()?,
+        "--hot-reload" => config.hot_reload = true,
+        "--texture" => config.texture = Some(value()?.into()),
+        "--instances" => config.instances = value()?.parse().map_err(|e| format!("--instances: {e}"))?,
+        "--particles" => config.particles = value()?.parse().map_err(|e| format!("--particles: {e}"))?,
+        "--camera" => config.camera = value()?.parse()?,
+        "--ortho" => config.ortho = true,
+        "--depth" => config.depth = parse_depth(&value()?)?,
+        "--msaa" => match value()?.parse::<u32>() {
+          Ok(count) if crate::targets::SAMPLE_COUNTS.contains(&count) => config.sample_count = count,
+          _ => return Err("--msaa expects 1, 2, 4 or 8".to_string()),
+        },
+        "--post" => {
+          config.post = value()?.split(',').map(str::parse).collect::<Result<_, _>>().map_err(|e| format!("--post: {e}"))?;
+        }
+        "--render-scale" => match value()?.parse::<f32>() {
+          Ok(scale) if scale.is_finite() && scale > 0.0 => config.render_scale = scale,
+          _ => return Err("--render-scale expects a positive factor".to_string()),
+        },
+        "--present-mode" => config.present_mode = renderer::parse_present_mode(&value()?)?,
+        "--frame-latency" => match value()?.parse::<u32>() {
+          Ok(frames) if frames > 0 => config.frame_latency = frames,
+          _ => return Err("--frame-latency expects at least 1 frame".to_string()),
+        },
+        "--max-fps" => match value()?.parse::<f64>() {
+          Ok(fps) if renderer::frame_interval(fps).is_some() => config.max_fps = Some(fps),
+          _ => return Err("--max-fps expects a positive rate".to_string()),
+        },
+        "--model" => config.model = Some(value()?.into()),
+        _ => return Err(format!("unknown argument `{arg}`")),
+      }
+    }
+
+    Ok(config)
+  }
+}
@@ -0,0 +1,55 @@
+// Depth/stencil target matching the size of the color target it is used with.
+pub(crate) struct DepthTexture {
+  // kept alive for `view`
+  _texture: wgpu::Texture,
+  view: wgpu::TextureView,
+  format: wgpu::TextureFormat,
+}
+
+impl DepthTexture {
+  pub fn new(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Depth Texture"),
+      size: wgpu::Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    Self { _texture: texture, view, format }
+  }
+
+  // Clears depth to the far plane, and stencil to 0 for formats that have one.
+  pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+    wgpu::RenderPassDepthStencilAttachment {
+      view: &self.view,
+      depth_ops: Some(wgpu::Operations {
+        load: wgpu::LoadOp::Clear(1.0),
+        store: wgpu::StoreOp::Store,
+      }),
+      stencil_ops: self.format.has_stencil_aspect().then_some(wgpu::Operations {
+        load: wgpu::LoadOp::Clear(0),
+        store: wgpu::StoreOp::Store,
+      }),
+    }
+  }
+}
+
+// Closer fragments win, the stencil is left alone.
+pub(crate) fn depth_stencil_state(format: wgpu::TextureFormat) -> wgpu::DepthStencilState {
+  wgpu::DepthStencilState {
+    format,
+    depth_write_enabled: true,
+    depth_compare: wgpu::CompareFunction::Less,
+    stencil: wgpu::StencilState::default(),
+    bias: wgpu::DepthBiasState::default(),
+  }
+}
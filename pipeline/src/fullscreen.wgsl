@@ -0,0 +1,15 @@
+// 后处理共用的顶点着色器：一个覆盖全屏的三角形
+// 片元着色器在各个效果的文件里，通过 @location(0) 读取 uv
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) uv: vec2f,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.uv = uv;
+  out.clip_position = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+  return out;
+}
@@ -0,0 +1,12 @@
+// 灰度：按亮度混合三个通道
+@group(0) @binding(0)
+var input_sampler: sampler;
+@group(0) @binding(1)
+var input: texture_2d<f32>;
+
+@fragment
+fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4f {
+  let color = textureSample(input, input_sampler, uv);
+  let luminance = dot(color.rgb, vec3f(0.2126, 0.7152, 0.0722));
+  return vec4f(vec3f(luminance), color.a);
+}
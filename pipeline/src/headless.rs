@@ -0,0 +1,123 @@
+use renderer::{CaptureError, CapturedFrame, DeviceError, Gpu, HeadlessRenderer};
+
+use crate::{
+  Camera, CameraUniform, Config, InstancedDraw, Instances, MeshData, RenderGraph, Texture, TexturedDraw,
+  TexturedVertex, TriangleScene, Vertex,
+};
+
+// Renders the triangle scene into an offscreen texture instead of a window
+// surface, so it can run in CI and on machines without a display or GPU.
+pub struct HeadlessState {
+  renderer: HeadlessRenderer,
+  scene: TriangleScene,
+}
+
+impl HeadlessState {
+  pub const FORMAT: wgpu::TextureFormat = HeadlessRenderer::FORMAT;
+
+  pub async fn new(width: u32, height: u32) -> Self {
+    Self::with_config(width, height, &Config::default()).await
+  }
+
+  pub async fn with_config(width: u32, height: u32, config: &Config) -> Self {
+    let gpu = Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults()).await
+      .expect("no adapter available for headless rendering");
+    let mut renderer = HeadlessRenderer::new(gpu, width, height);
+    renderer.set_render_scale(config.render_scale);
+    let (width, height) = renderer.size();
+    let scene = TriangleScene::new(renderer.gpu(), Self::FORMAT, width, height, config)
+      .unwrap_or_else(|e| panic!("[headless]: {e}"));
+
+    Self { renderer, scene }
+  }
+
+  // For creating resources such as a `Texture` to render with.
+  pub fn device(&self) -> &wgpu::Device {
+    &self.renderer.gpu().device
+  }
+
+  pub fn queue(&self) -> &wgpu::Queue {
+    &self.renderer.gpu().queue
+  }
+
+  pub fn get_adapter_info(&self) -> wgpu::AdapterInfo {
+    self.renderer.gpu().adapter_info()
+  }
+
+  pub fn size(&self) -> (u32, u32) {
+    self.renderer.size()
+  }
+
+  // What `Config::sample_count` ended up as on this adapter.
+  pub fn sample_count(&self) -> u32 {
+    self.scene.targets.sample_count()
+  }
+
+  // The post-processing passes from `Config::post`, e.g. for `RenderGraph::set_params`.
+  pub fn post(&self) -> Option<&RenderGraph> {
+    self.scene.post.as_ref()
+  }
+
+  // Used by the following `render` calls.
+  pub fn set_camera(&mut self, camera: &Camera) {
+    self.scene.camera = *camera;
+    self.scene.camera_binding.write(&self.renderer.gpu().queue, &CameraUniform::from_camera(camera));
+  }
+
+  // Replaces the geometry drawn by the following `render` calls.
+  pub fn set_mesh(&mut self, data: &MeshData<Vertex>) {
+    self.scene.mesh = data.upload(&self.renderer.gpu().device, "Headless Mesh");
+    self.scene.mesh_data = data.clone();
+    self.scene.textured = None;
+  }
+
+  // Draws `data` with `texture` instead of the colored mesh.
+  pub fn set_textured_mesh(&mut self, data: &MeshData<TexturedVertex>, texture: Texture) {
+    let scene = &mut self.scene;
+    scene.textured = Some(TexturedDraw::new(
+      &self.renderer.gpu().device,
+      &scene.targets,
+      &scene.camera_binding.bind_group_layout,
+      &scene.sources.textured,
+      data.clone(),
+      texture,
+    ).unwrap_or_else(|e| panic!("[headless]: {e}")));
+  }
+
+  pub fn is_device_lost(&self) -> bool {
+    self.renderer.device().is_lost()
+  }
+
+  // Recreates a lost device and everything the scene drew with on it, see `Scene::recreate`.
+  // Returns whether the device was lost.
+  pub fn recover_device(&mut self) -> Result<bool, DeviceError> {
+    if !self.renderer.recover_device()? {
+      return Ok(false);
+    }
+    self.scene.rebuild(self.renderer.gpu())?;
+    Ok(true)
+  }
+
+  // Lets `f` add, remove or update instances of the colored mesh, then uploads what changed
+  // and returns the number of bytes written to the instance buffer.
+  pub fn update_instances(&mut self, f: impl FnOnce(&mut Instances)) -> u64 {
+    let Gpu { device, queue, .. } = self.renderer.gpu();
+    let scene = &mut self.scene;
+    let instanced = scene.instanced.get_or_insert_with(|| {
+      let camera_layout = &scene.camera_binding.bind_group_layout;
+      InstancedDraw::new(device, &scene.targets, camera_layout, &scene.sources.instanced, Instances::new())
+        .unwrap_or_else(|e| panic!("[headless]: {e}"))
+    });
+    f(&mut instanced.instances);
+    instanced.instances.flush(device, queue)
+  }
+
+  // Renders one frame and returns it as tightly packed RGBA8 rows, top row first.
+  pub fn render(&self) -> Vec<u8> {
+    self.capture_frame().expect("failed to read back headless frame").pixels
+  }
+
+  pub fn capture_frame(&self) -> Result<CapturedFrame, CaptureError> {
+    self.renderer.capture(&self.scene)
+  }
+}
@@ -0,0 +1,51 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::mpsc::{self, Receiver},
+};
+
+use notify::{RecursiveMode, Watcher};
+
+// Watches shader source files and reports when any of them changed.
+pub(crate) struct ShaderWatcher {
+  paths: Vec<PathBuf>,
+  events: Receiver<notify::Result<notify::Event>>,
+  // dropping the watcher stops the notifications
+  _watcher: notify::RecommendedWatcher,
+}
+
+impl ShaderWatcher {
+  pub fn new(paths: Vec<PathBuf>) -> notify::Result<Self> {
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    // editors often save by replacing the file, so watch the directories instead of the files
+    let mut dirs: Vec<&Path> = paths.iter().filter_map(|path| path.parent()).collect();
+    dirs.dedup();
+    for dir in dirs {
+      watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(Self { paths, events, _watcher: watcher })
+  }
+
+  pub fn paths(&self) -> &[PathBuf] {
+    &self.paths
+  }
+
+  // Drains pending notifications, true if a watched file was modified since the last call.
+  pub fn changed(&self) -> bool {
+    let mut changed = false;
+    for event in self.events.try_iter() {
+      match event {
+        Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+          changed |= event.paths.iter().any(|path| {
+            self.paths.iter().any(|watched| path.file_name() == watched.file_name())
+          });
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("[hot-reload]: {}", e),
+      }
+    }
+    changed
+  }
+}
@@ -0,0 +1,218 @@
+use std::ops::Range;
+
+use crate::vertex_layout;
+
+// Per-instance transform and tint, read by `instanced.wgsl` from vertex buffer slot 1.
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct Instance {
+  pub position: [f32; 3],
+  pub scale: f32,
+  // unit quaternion, xyzw
+  pub rotation: [f32; 4],
+  // multiplies the vertex color
+  pub color: [f32; 4],
+}
+
+vertex_layout!(Instance, Instance {
+  position => 2: Float32x3,
+  scale => 3: Float32,
+  rotation => 4: Float32x4,
+  color => 5: Float32x4,
+});
+
+impl Default for Instance {
+  fn default() -> Self {
+    Self {
+      position: [0.0; 3],
+      scale: 1.0,
+      rotation: [0.0, 0.0, 0.0, 1.0],
+      color: [1.0; 4],
+    }
+  }
+}
+
+impl Instance {
+  pub fn new(position: [f32; 3], scale: f32, color: [f32; 4]) -> Self {
+    Self { position, scale, color, ..Default::default() }
+  }
+}
+
+// Stays valid until the instance is removed, unlike its index which `remove` can change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceId(u32);
+
+struct GpuInstances {
+  buffer: wgpu::Buffer,
+  capacity: usize,
+}
+
+// Instances kept on the CPU and mirrored into a vertex buffer. Changes are recorded as
+// index ranges and `flush` writes only those, unless the buffer has to grow.
+#[derive(Default)]
+pub struct Instances {
+  instances: Vec<Instance>,
+  // index -> id
+  ids: Vec<InstanceId>,
+  // id -> index, `None` for removed ids waiting in `free_ids`
+  slots: Vec<Option<usize>>,
+  free_ids: Vec<InstanceId>,
+  dirty: Vec<Range<usize>>,
+  gpu: Option<GpuInstances>,
+}
+
+impl Instances {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.instances.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.instances.is_empty()
+  }
+
+  pub fn as_slice(&self) -> &[Instance] {
+    &self.instances
+  }
+
+  pub fn get(&self, id: InstanceId) -> Option<&Instance> {
+    self.index(id).map(|index| &self.instances[index])
+  }
+
+  fn index(&self, id: InstanceId) -> Option<usize> {
+    self.slots.get(id.0 as usize).copied().flatten()
+  }
+
+  pub fn add(&mut self, instance: Instance) -> InstanceId {
+    let index = self.instances.len();
+    let id = match self.free_ids.pop() {
+      Some(id) => id,
+      None => {
+        self.slots.push(None);
+        InstanceId(self.slots.len() as u32 - 1)
+      }
+    };
+    self.slots[id.0 as usize] = Some(index);
+    self.ids.push(id);
+    self.instances.push(instance);
+    self.dirty.push(index..index + 1);
+    id
+  }
+
+  // Moves the last instance into the gap, so only that one slot has to be written again.
+  pub fn remove(&mut self, id: InstanceId) -> Option<Instance> {
+    let index = self.index(id)?;
+    self.slots[id.0 as usize] = None;
+    self.free_ids.push(id);
+
+    let instance = self.instances.swap_remove(index);
+    self.ids.swap_remove(index);
+    if index < self.instances.len() {
+      self.slots[self.ids[index].0 as usize] = Some(index);
+      self.dirty.push(index..index + 1);
+    }
+    Some(instance)
+  }
+
+  // Returns false if `id` was removed.
+  pub fn update(&mut self, id: InstanceId, instance: Instance) -> bool {
+    let Some(index) = self.index(id) else { return false };
+    self.instances[index] = instance;
+    self.dirty.push(index..index + 1);
+    true
+  }
+
+  pub fn clear(&mut self) {
+    *self = Self { gpu: self.gpu.take(), ..Self::default() };
+  }
+
+  // The index ranges `flush` would write, sorted and merged, ignoring anything past the end.
+  pub fn dirty_ranges(&self) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = self.dirty.iter()
+      .map(|range| range.start..range.end.min(self.instances.len()))
+      .filter(|range| !range.is_empty())
+      .collect();
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+      match merged.last_mut() {
+        Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+        _ => merged.push(range),
+      }
+    }
+    merged
+  }
+
+  // Uploads the changes since the last flush and returns the number of bytes written.
+  pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> u64 {
+    let stride = std::mem::size_of::<Instance>();
+
+    let needs_new_buffer = self.gpu.as_ref().is_none_or(|gpu| gpu.capacity < self.instances.len());
+    let ranges = if needs_new_buffer {
+      let capacity = self.instances.len().next_power_of_two().max(64);
+      log::info!("[instances]: allocating room for {} instances", capacity);
+      self.gpu = Some(GpuInstances {
+        buffer: device.create_buffer(&wgpu::BufferDescriptor {
+          label: Some("Instance Buffer"),
+          size: (capacity * stride) as wgpu::BufferAddress,
+          usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+          mapped_at_creation: false,
+        }),
+        capacity,
+      });
+      std::iter::once(0..self.instances.len()).collect()
+    } else {
+      self.dirty_ranges()
+    };
+    self.dirty.clear();
+
+    let buffer = &self.gpu.as_ref().unwrap().buffer;
+    let mut written = 0;
+    for range in ranges.into_iter().filter(|range| !range.is_empty()) {
+      let bytes = bytemuck::cast_slice(&self.instances[range.clone()]);
+      queue.write_buffer(buffer, (range.start * stride) as wgpu::BufferAddress, bytes);
+      written += bytes.len() as u64;
+    }
+    written
+  }
+
+  // Forgets the instance buffer, e.g. when its device was lost, so the next `flush` uploads
+  // every instance to a new one.
+  pub fn discard_buffer(&mut self) {
+    self.gpu = None;
+  }
+
+  // `None` until the first `flush`.
+  pub fn buffer(&self) -> Option<&wgpu::Buffer> {
+    self.gpu.as_ref().map(|gpu| &gpu.buffer)
+  }
+}
+
+impl FromIterator<Instance> for Instances {
+  fn from_iter<I: IntoIterator<Item = Instance>>(iter: I) -> Self {
+    let mut instances = Self::new();
+    for instance in iter {
+      instances.add(instance);
+    }
+    instances
+  }
+}
+
+// `count` instances on a square grid filling [-1, 1], tinted along x and y.
+pub fn instance_grid(count: u32) -> Instances {
+  let side = (count as f32).sqrt().ceil().max(1.0) as u32;
+  let cell = 2.0 / side as f32;
+  (0..count).map(|i| {
+    let (x, y) = (i % side, i / side);
+    let (u, v) = ((x as f32 + 0.5) / side as f32, (y as f32 + 0.5) / side as f32);
+    Instance::new(
+      [u * 2.0 - 1.0, v * 2.0 - 1.0, 0.0],
+      cell * 0.8,
+      [0.25 + 0.75 * u, 0.25 + 0.75 * v, 1.0, 1.0],
+    )
+  }).collect()
+}
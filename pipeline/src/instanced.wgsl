@@ -0,0 +1,49 @@
+// 顶点着色器
+struct CameraUniform {
+    view_proj: mat4x4f,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3f,
+    @location(1) color: vec3f,
+};
+
+// 每个实例的变换和颜色
+struct InstanceInput {
+    @location(2) position: vec3f,
+    @location(3) scale: f32,
+    @location(4) rotation: vec4f,
+    @location(5) color: vec4f,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) color: vec4f,
+};
+
+// 用单位四元数旋转向量
+fn rotate(q: vec4f, v: vec3f) -> vec3f {
+  let t = 2.0 * cross(q.xyz, v);
+  return v + q.w * t + cross(q.xyz, t);
+}
+
+@vertex
+fn vs_main(
+  in: VertexInput,
+  instance: InstanceInput,
+) -> VertexOutput {
+  var out: VertexOutput;
+  out.color = vec4f(in.color, 1.0) * instance.color;
+  let world = rotate(instance.rotation, in.position * instance.scale) + instance.position;
+  out.clip_position = camera.view_proj * vec4f(world, 1.0);
+  return out;
+}
+
+// 片元着色器
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    return in.color;
+}
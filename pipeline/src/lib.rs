@@ -1,95 +1,151 @@
-use app_surface::{AppSurface, SurfaceFrame};
+use std::time::Duration;
+
+use renderer::{DeviceError, Frame, Gpu, GpuTimer, Instant, RunOptions, Scene};
 
 #[cfg(target_arch="wasm32")]
 use wasm_bindgen::prelude::*;
 
-use wgpu::{include_wgsl, util::DeviceExt};
-use winit::{
-  dpi::PhysicalSize, event::*, event_loop::{ControlFlow, EventLoop}, window::WindowBuilder
-};
-
-struct State {
-  app: AppSurface,
+use winit::event::WindowEvent;
+
+mod camera;
+pub use camera::{Camera, CameraController, CameraUniform, FlyController, OrbitController, Projection};
+use camera::CameraBinding;
+
+mod compute;
+pub use compute::{dispatch_size, supports_compute, ComputeError, ComputeKernel, StorageBuffer};
+#[cfg(not(target_arch = "wasm32"))]
+pub use compute::HeadlessCompute;
+
+mod config;
+pub use config::{CameraMode, Config, DepthFormat, Effect, ShaderLang};
+
+mod depth;
+mod targets;
+pub use targets::supported_sample_count;
+use targets::RenderTargets;
+
+mod instance;
+pub use instance::{instance_grid, Instance, InstanceId, Instances};
+
+mod mesh;
+pub use mesh::{Indices, Mesh, MeshData};
+
+mod particles;
+pub use particles::{particle_fountain, Particle, ParticleSimulation, SimParams};
+#[cfg(not(target_arch = "wasm32"))]
+pub use particles::simulate_headless;
+
+pub mod post;
+pub use post::{FullscreenPass, GraphError, RenderGraph};
+
+pub mod reflect;
+mod shader;
+pub use shader::ShaderError;
+use shader::{ShaderModules, WgslSource};
+mod texture;
+pub use texture::{Texture, TextureError};
+pub mod vertex;
+pub use vertex::VertexLayout;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use renderer::{CaptureError, CapturedFrame};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::HeadlessState;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+#[cfg(not(target_arch = "wasm32"))]
+mod loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub use loader::{LoadError, Model, ModelMesh};
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shader_check;
+
+// the longest time the camera and the particles move for in one update
+const MAX_UPDATE_STEP: Duration = Duration::from_millis(100);
+
+// The triangle with whatever `Config` adds to it, drawn by the windowed renderer and by
+// `HeadlessState`.
+struct TriangleScene {
+  shader: ShaderLang,
+  size: (u32, u32),
+  // the sources of the pipelines, the last ones that reloaded fine
+  sources: shader::SceneSources,
   pipline: wgpu::RenderPipeline,
-  vertex_buffer: wgpu::Buffer,
-  num_vertices: u32,
+  mesh_data: MeshData<Vertex>,
+  mesh: Mesh,
+  camera: Camera,
+  camera_controller: CameraController,
+  camera_binding: CameraBinding,
+  last_update: Option<Instant>,
+  targets: RenderTargets,
+  textured: Option<TexturedDraw>,
+  instanced: Option<InstancedDraw>,
+  particles: Option<ParticleDraw>,
+  post: Option<RenderGraph>,
+  #[cfg(not(target_arch = "wasm32"))]
+  shader_watcher: Option<hot_reload::ShaderWatcher>,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
-struct Vertex {
-  position: [f32; 3],
-  color: [f32; 3],
-}
-
-impl Vertex {
-  fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-    wgpu::VertexBufferLayout { 
-      array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-      step_mode: wgpu::VertexStepMode::Vertex,
-      attributes: &[
-        wgpu::VertexAttribute {
-          offset: 0,
-          shader_location: 0,
-          format: wgpu::VertexFormat::Float32x3,
-        },
-        wgpu::VertexAttribute {
-          offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-          shader_location: 1,
-          format: wgpu::VertexFormat::Float32x3,
-        }
-      ]
-    }
-  }
+pub struct Vertex {
+  pub position: [f32; 3],
+  pub color: [f32; 3],
 }
 
+vertex_layout!(Vertex {
+  position => 0: Float32x3,
+  color => 1: Float32x3,
+});
 
-const VERTICES: &[Vertex] = &[
-  Vertex { position: [ 0.00, 0.50, 0.00], color: [1.0, 0.0, 0.0] },
-  Vertex { position: [-0.58,-0.50, 0.00], color: [0.0, 1.0, 0.0] },
-  Vertex { position: [ 0.58,-0.50, 0.00], color: [0.0, 0.0, 1.0] },
-];
-
-impl State {
-  fn new(app: AppSurface) -> Self {
-
-    let num_vertices = VERTICES.len() as u32;
-
-    let raw_vertices = unsafe {
-      core::slice::from_raw_parts(VERTICES.as_ptr() as *const u8, std::mem::size_of::<Vertex>() * num_vertices as usize)
-    };
-
-    let vertex_buffer: wgpu::Buffer = app.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-      label: Some("Vertex Buffer"),
-      contents: &raw_vertices,
-      usage: wgpu::BufferUsages::VERTEX,
-    });
-
-    let shader = app.device.create_shader_module(include_wgsl!("triangle.wgsl"));
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct TexturedVertex {
+  pub position: [f32; 3],
+  pub uv: [f32; 2],
+}
 
-    let pipeline_layout = app.device.create_pipeline_layout(
+vertex_layout!(TexturedVertex {
+  position => 0: Float32x3,
+  uv => 1: Float32x2,
+});
+
+// Validation errors, e.g. shaders that don't fit the vertex layout, are returned instead of
+// reaching the uncaptured error handler.
+fn create_pipeline(
+  device: &wgpu::Device,
+  label: &str,
+  targets: &RenderTargets,
+  shaders: &ShaderModules,
+  buffers: &[wgpu::VertexBufferLayout],
+  bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> Result<wgpu::RenderPipeline, DeviceError> {
+  renderer::validate(device, &format!("{label} Pipeline"), || {
+    let pipeline_layout = device.create_pipeline_layout(
       &wgpu::PipelineLayoutDescriptor {
-        label: Some("Triangle Glsl Pipline Layout"),
-        bind_group_layouts: &[],
+        label: Some(&format!("{label} Pipline Layout")),
+        bind_group_layouts,
         push_constant_ranges: &[]
     });
 
-    let pipline = app.device.create_render_pipeline(
+    device.create_render_pipeline(
       &wgpu::RenderPipelineDescriptor {
-        label: Some("Triangle Glsl Pipeline"),
+        label: Some(&format!("{label} Pipeline")),
         layout: Some(&pipeline_layout),
         vertex: wgpu::VertexState {
-          module: &shader,
-          entry_point: "vs_main",
-          buffers: &[
-            Vertex::desc(),
-          ]
+          module: shaders.vertex(),
+          entry_point: shaders.vertex_entry,
+          buffers,
         },
         fragment: Some(wgpu::FragmentState {
-          module: &shader,
-          entry_point: "fs_main",
+          module: shaders.fragment(),
+          entry_point: shaders.fragment_entry,
           targets: &[Some(wgpu::ColorTargetState {
-            format: app.config.format.add_srgb_suffix(),
+            format: targets.color_format(),
             blend: Some(wgpu::BlendState::REPLACE),
             write_mask: wgpu::ColorWrites::ALL
           })]
@@ -103,161 +159,652 @@ impl State {
           polygon_mode: wgpu::PolygonMode::Fill,
           conservative: false
         },
-        depth_stencil: None,
+        depth_stencil: targets.depth_format().map(depth::depth_stencil_state),
         multisample: wgpu::MultisampleState {
-          count: 1,
+          count: targets.sample_count(),
           mask: !0,
           alpha_to_coverage_enabled: false
         },
         multiview: None
-    });
+    })
+  })
+}
 
-    Self { app, pipline, vertex_buffer, num_vertices }
+// Why a draw of the scene couldn't be created: naga rejected its shaders, or wgpu the pipeline.
+#[derive(Debug)]
+enum PipelineError {
+  Shader(ShaderError),
+  Device(DeviceError),
+}
+
+impl From<ShaderError> for PipelineError {
+  fn from(e: ShaderError) -> Self {
+    PipelineError::Shader(e)
   }
+}
 
+impl From<DeviceError> for PipelineError {
+  fn from(e: DeviceError) -> Self {
+    PipelineError::Device(e)
+  }
+}
 
-  fn get_adapter_info(&self) -> wgpu::AdapterInfo {
-    self.app.adapter.get_info()
+impl std::fmt::Display for PipelineError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PipelineError::Shader(e) => write!(f, "{e}"),
+      PipelineError::Device(e) => write!(f, "{e}"),
+    }
   }
+}
 
-  fn resize(&mut self, size: &PhysicalSize<u32>) {
-    if size.width == 0 || size.height == 0 { return };
+// naga accepted the shaders, but wgpu can still reject the modules, so they are validated too.
+fn triangle_pipeline(
+  device: &wgpu::Device,
+  targets: &RenderTargets,
+  camera_layout: &wgpu::BindGroupLayout,
+  sources: &shader::ShaderSources,
+) -> Result<wgpu::RenderPipeline, PipelineError> {
+  let shaders = renderer::validate(device, "Triangle Shaders", || shader::triangle_shaders(device, sources))??;
+  Ok(create_pipeline(device, "Triangle", targets, &shaders, &[Vertex::desc()], &[camera_layout])?)
+}
 
-    let pixel_width = ((size.width as f64) / self.app.get_view().scale_factor()).round() as u32;
-    let pixel_height = ((size.height as f64) / self.app.get_view().scale_factor()).round() as u32;
+fn textured_pipeline(
+  device: &wgpu::Device,
+  targets: &RenderTargets,
+  camera_layout: &wgpu::BindGroupLayout,
+  texture_layout: &wgpu::BindGroupLayout,
+  source: &WgslSource,
+) -> Result<wgpu::RenderPipeline, PipelineError> {
+  let shaders = renderer::validate(device, "Textured Shaders", || shader::textured_shaders(device, source))??;
+  Ok(create_pipeline(
+    device,
+    "Textured",
+    targets,
+    &shaders,
+    &[TexturedVertex::desc()],
+    &[camera_layout, texture_layout],
+  )?)
+}
 
-    log::info!("[resize]: target_width {} target_height {}", size.width, size.height);
-    log::info!("[resize]: scale_factor {}", self.app.get_view().scale_factor());
-    log::info!("[resize]: set_width {} set_height {}", pixel_width, pixel_height);
-    log::info!("[resize]: current_width {} current_height {}", self.app.config.width, self.app.config.height);
+// The shader files on disk with `--hot-reload`, so they can be edited before starting too, else
+// the ones baked in.
+fn scene_sources(config: &Config) -> shader::SceneSources {
+  #[cfg(not(target_arch = "wasm32"))]
+  if config.hot_reload {
+    match shader::SceneSources::load(config.shader) {
+      Ok(sources) => return sources,
+      Err(e) => log::error!("[hot-reload]: couldn't read shaders, using the built in ones: {}", e),
+    }
+  }
+  shader::SceneSources::builtin(config.shader)
+}
 
-    if self.app.config.width == pixel_width && self.app.config.height == pixel_height {
-      return;
+// Switches `current` to `loaded` if it differs and `build` accepts it, returning what was built.
+// Otherwise `current` stays, along with the pipeline built from it.
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_source<S: PartialEq, T>(
+  current: &mut S,
+  loaded: S,
+  label: &str,
+  build: impl FnOnce(&S) -> Result<T, PipelineError>,
+) -> Option<T> {
+  if *current == loaded {
+    return None;
+  }
+  match build(&loaded) {
+    Ok(built) => {
+      log::info!("[hot-reload]: {} shaders reloaded", label);
+      *current = loaded;
+      Some(built)
+    }
+    Err(e) => {
+      log::error!("[hot-reload]: keeping the last good {} pipeline\n{}", label, e);
+      None
     }
-    self.app.resize_surface()
   }
+}
 
-  fn request_redraw(&mut self) {
-    self.app.get_view().request_redraw();
+// The model given with `--model`, or the triangle when there is none or it can't be loaded.
+fn mesh_data(#[allow(unused_variables)] config: &Config) -> MeshData<Vertex> {
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(path) = &config.model {
+    match Model::load(path) {
+      Ok(mut model) => {
+        model.fit_to_unit_cube();
+        let data = model.to_mesh_data();
+        log::info!("[model]: loaded {} ({} meshes, {} vertices)", path.display(), model.meshes.len(), data.vertices.len());
+        return data;
+      }
+      Err(e) => log::error!("[model]: {}, drawing the triangle instead", e),
+    }
   }
+  MeshData::triangle()
+}
 
-  fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+// A mesh with the pipeline to draw it, the bind group it reads at @group(1) and
+// the instance buffer for vertex buffer slot 1 with its instance count, if any.
+struct Draw<'a> {
+  pipline: &'a wgpu::RenderPipeline,
+  bind_group: Option<&'a wgpu::BindGroup>,
+  mesh: &'a Mesh,
+  instances: Option<(&'a wgpu::Buffer, u32)>,
+}
 
-    let (output, view) = self.app.get_current_frame_view(Some(self.app.config.format.add_srgb_suffix()));
+// A textured mesh, drawn instead of the colored one when there is a texture.
+struct TexturedDraw {
+  pipline: wgpu::RenderPipeline,
+  data: MeshData<TexturedVertex>,
+  mesh: Mesh,
+  texture: Texture,
+  // kept for building `pipline` again when its shader is reloaded
+  texture_layout: wgpu::BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+}
+
+impl TexturedDraw {
+  fn new(
+    device: &wgpu::Device,
+    targets: &RenderTargets,
+    camera_layout: &wgpu::BindGroupLayout,
+    source: &WgslSource,
+    data: MeshData<TexturedVertex>,
+    texture: Texture,
+  ) -> Result<Self, PipelineError> {
+    let texture_layout = Texture::bind_group_layout(device);
+    let pipline = textured_pipeline(device, targets, camera_layout, &texture_layout, source)?;
+
+    Ok(Self {
+      pipline,
+      mesh: data.upload(device, "Textured"),
+      data,
+      bind_group: texture.bind_group(device, &texture_layout),
+      texture,
+      texture_layout,
+    })
+  }
+
+  // The same draw on another device, `None` if it can't be created there.
+  fn recreate(
+    &self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    targets: &RenderTargets,
+    camera_layout: &wgpu::BindGroupLayout,
+    source: &WgslSource,
+  ) -> Option<Self> {
+    let texture = self.texture.recreate(device, queue)
+      .map_err(|e| log::error!("[texture]: {}, drawing without it", e))
+      .ok()?;
+    Self::new(device, targets, camera_layout, source, self.data.clone(), texture)
+      .map_err(|e| log::error!("[texture]: {}, drawing without it", e))
+      .ok()
+  }
 
-    let mut encoder = self.app.device.create_command_encoder(
-      &wgpu::CommandEncoderDescriptor {
-        label: Some("Render Encoder")
+  // The quad with the texture given with `--texture`, `None` when there is none or it can't be loaded.
+  #[allow(unused_variables)]
+  fn from_config(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    targets: &RenderTargets,
+    camera_layout: &wgpu::BindGroupLayout,
+    source: &WgslSource,
+    config: &Config,
+  ) -> Option<Self> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &config.texture {
+      match Texture::from_path(device, queue, path) {
+        Ok(texture) => {
+          log::info!("[texture]: loaded {} with {} mip levels", path.display(), texture.mip_level_count());
+          match Self::new(device, targets, camera_layout, source, MeshData::textured_quad(), texture) {
+            Ok(textured) => return Some(textured),
+            Err(e) => log::error!("[texture]: {}, drawing without it", e),
+          }
+        }
+        Err(e) => log::error!("[texture]: {}, drawing without it", e),
       }
-    );
-
-    {
-      let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: Some("First Render Pass"),
-        color_attachments: &[Some(wgpu::RenderPassColorAttachment{
-          view: &view,
-          resolve_target: None,
-          ops: wgpu::Operations {
-            load: wgpu::LoadOp::Clear(wgpu::Color {
-              r: 0.1, g: 0.2, b: 0.3, a: 1.0
-            }),
-            store: wgpu::StoreOp::Store
-          },
-        })],
-        ..Default::default()
-      });
-
-      render_pass.set_pipeline(&self.pipline);
-      render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-      render_pass.draw(0..self.num_vertices, 0..1);
     }
+    None
+  }
 
+  fn draw(&self) -> Draw<'_> {
+    Draw { pipline: &self.pipline, bind_group: Some(&self.bind_group), mesh: &self.mesh, instances: None }
+  }
+}
 
-    self.app.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
+fn instanced_pipeline(
+  device: &wgpu::Device,
+  targets: &RenderTargets,
+  camera_layout: &wgpu::BindGroupLayout,
+  source: &WgslSource,
+) -> Result<wgpu::RenderPipeline, PipelineError> {
+  let shaders = renderer::validate(device, "Instanced Shaders", || shader::instanced_shaders(device, source))??;
+  Ok(create_pipeline(
+    device,
+    "Instanced",
+    targets,
+    &shaders,
+    &[Vertex::desc(), Instance::desc()],
+    &[camera_layout],
+  )?)
+}
 
-    Ok(())
+// Copies of the colored mesh, one per instance, in a single draw call.
+struct InstancedDraw {
+  pipline: wgpu::RenderPipeline,
+  instances: Instances,
+}
+
+impl InstancedDraw {
+  fn new(
+    device: &wgpu::Device,
+    targets: &RenderTargets,
+    camera_layout: &wgpu::BindGroupLayout,
+    source: &WgslSource,
+    instances: Instances,
+  ) -> Result<Self, PipelineError> {
+    Ok(Self { pipline: instanced_pipeline(device, targets, camera_layout, source)?, instances })
+  }
+
+  // `None` until the instances are flushed.
+  fn draw<'a>(&'a self, mesh: &'a Mesh) -> Option<Draw<'a>> {
+    let buffer = self.instances.buffer()?;
+    Some(Draw {
+      pipline: &self.pipline,
+      bind_group: None,
+      mesh,
+      instances: Some((buffer, self.instances.len() as u32)),
+    })
   }
 }
 
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
-pub async fn run() {
-  cfg_if::cfg_if! {
-    if #[cfg(target_arch="wasm32")] {
-      std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-      console_log::init_with_level(log::Level::Info).expect("Couldn't initialize logger");
+// The colored mesh drawn once per particle, where the particle simulation put it.
+struct ParticleDraw {
+  pipline: wgpu::RenderPipeline,
+  simulation: ParticleSimulation,
+}
+
+impl ParticleDraw {
+  fn new(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    targets: &RenderTargets,
+    camera_layout: &wgpu::BindGroupLayout,
+    source: &WgslSource,
+    count: u32,
+  ) -> Result<Self, PipelineError> {
+    let pipline = instanced_pipeline(device, targets, camera_layout, source)?;
+    let simulation = ParticleSimulation::new(device, queue, SimParams::default(), &particle_fountain(count));
+    Ok(Self { pipline, simulation })
+  }
+
+  fn draw<'a>(&'a self, mesh: &'a Mesh) -> Draw<'a> {
+    Draw {
+      pipline: &self.pipline,
+      bind_group: None,
+      mesh,
+      instances: Some((self.simulation.instance_buffer(), self.simulation.len() as u32)),
+    }
+  }
+}
+
+// What the windowed and the headless state draw: the textured quad if there is one, else the
+// colored mesh, instanced if there are instances.
+fn scene_draw<'a>(
+  pipline: &'a wgpu::RenderPipeline,
+  mesh: &'a Mesh,
+  textured: Option<&'a TexturedDraw>,
+  instanced: Option<&'a InstancedDraw>,
+) -> Option<Draw<'a>> {
+  match (textured, instanced) {
+    (Some(textured), _) => Some(textured.draw()),
+    (None, Some(instanced)) => instanced.draw(mesh),
+    (None, None) => Some(Draw { pipline, bind_group: None, mesh, instances: None }),
+  }
+}
+
+// The scene pass, drawing `draws` over the clear color.
+fn encode_render_pass(
+  encoder: &mut wgpu::CommandEncoder,
+  view: &wgpu::TextureView,
+  targets: &RenderTargets,
+  camera: &wgpu::BindGroup,
+  draws: &[Draw],
+  timer: Option<&GpuTimer>,
+) {
+  let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+    label: Some("First Render Pass"),
+    color_attachments: &[Some(targets.color_attachment(view, wgpu::Operations {
+      load: wgpu::LoadOp::Clear(wgpu::Color {
+        r: 0.1, g: 0.2, b: 0.3, a: 1.0
+      }),
+      store: wgpu::StoreOp::Store
+    }))],
+    depth_stencil_attachment: targets.depth_attachment(),
+    timestamp_writes: timer.and_then(|timer| timer.render_pass_writes("First Render Pass")),
+    ..Default::default()
+  });
+
+  render_pass.set_bind_group(0, camera, &[]);
+  for draw in draws {
+    render_pass.set_pipeline(draw.pipline);
+    if let Some(bind_group) = draw.bind_group {
+      render_pass.set_bind_group(1, bind_group, &[]);
+    }
+    let instances = match draw.instances {
+      Some((_, 0)) => continue,
+      Some((buffer, count)) => {
+        render_pass.set_vertex_buffer(1, buffer.slice(..));
+        0..count
+      }
+      None => 0..1,
+    };
+    draw.mesh.draw(&mut render_pass, instances);
+  }
+}
+
+// Draws the scene into `view`, through the post-processing passes if there are any.
+// Shared by the windowed renderer and the offscreen `HeadlessState`.
+fn encode_frame(
+  encoder: &mut wgpu::CommandEncoder,
+  frame: &Frame,
+  targets: &RenderTargets,
+  camera: &wgpu::BindGroup,
+  draws: &[Draw],
+  post: Option<&RenderGraph>,
+) {
+  match post {
+    Some(post) => {
+      encode_render_pass(encoder, post.scene_view(), targets, camera, draws, frame.timer);
+      post.encode(encoder, frame.view, frame.timer);
+    }
+    None => encode_render_pass(encoder, frame.view, targets, camera, draws, frame.timer),
+  }
+}
+
+impl TriangleScene {
+  // Fails only if the triangle pipeline does, what `Config` adds is left out when it can't be created.
+  fn new(gpu: &Gpu, color_format: wgpu::TextureFormat, width: u32, height: u32, config: &Config) -> Result<Self, PipelineError> {
+    let Gpu { adapter, device, queue } = gpu;
+    let mesh_data = mesh_data(config);
+    let mesh = mesh_data.upload(device, "Triangle");
+
+    // the default camera frames the z = 0 plane like clip space, so the triangle looks the same
+    let projection = if config.ortho { Projection::orthographic() } else { Projection::perspective() };
+    let camera = Camera { aspect: width as f32 / height as f32, ..Camera::new(projection) };
+    let camera_controller = match config.camera {
+      CameraMode::Orbit => CameraController::Orbit(OrbitController::new(&camera)),
+      CameraMode::Fly => CameraController::Fly(FlyController::new(&camera)),
+    };
+    let camera_binding = CameraBinding::new(device, &CameraUniform::from_camera(&camera));
+
+    let post = if config.post.is_empty() {
+      None
+    } else {
+      match RenderGraph::post_process(device, color_format, width, height, &config.post) {
+        Ok(post) => Some(post),
+        Err(e) => {
+          log::error!("[post]: {}, drawing without post-processing", e);
+          None
+        }
+      }
+    };
+
+    // with post-processing the scene is drawn into the graph's first target instead of the surface
+    let color_format = post.as_ref().map_or(color_format, RenderGraph::target_format);
+    let depth_format = config.depth.map(DepthFormat::texture_format);
+    let formats: Vec<_> = std::iter::once(color_format).chain(depth_format).collect();
+    let sample_count = supported_sample_count(adapter, device, &formats, config.sample_count);
+    let targets = RenderTargets::new(device, width, height, color_format, depth_format, sample_count);
+
+    let sources = scene_sources(config);
+    let camera_layout = &camera_binding.bind_group_layout;
+    let pipline = triangle_pipeline(device, &targets, camera_layout, &sources.triangle)?;
+
+    let textured = TexturedDraw::from_config(device, queue, &targets, camera_layout, &sources.textured, config);
+    let mut instanced = if config.instances == 0 {
+      None
     } else {
-      // init log to print info to stdout
-      std::env::set_var("RUST_LOG", "info");
-      env_logger::init();
+      match InstancedDraw::new(device, &targets, camera_layout, &sources.instanced, instance_grid(config.instances)) {
+        Ok(instanced) => Some(instanced),
+        Err(e) => {
+          log::error!("[instances]: {}, drawing without instances", e);
+          None
+        }
+      }
+    };
+    if let Some(instanced) = &mut instanced {
+      instanced.instances.flush(device, queue);
     }
+    let particles = if config.particles == 0 {
+      None
+    } else if !compute::supports_compute(adapter, device) {
+      log::error!("[particles]: {:?} can't run compute shaders, drawing without particles", adapter.get_info().backend);
+      None
+    } else {
+      match ParticleDraw::new(device, queue, &targets, camera_layout, &sources.instanced, config.particles) {
+        Ok(particles) => Some(particles),
+        Err(e) => {
+          log::error!("[particles]: {}, drawing without particles", e);
+          None
+        }
+      }
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let shader_watcher = if config.hot_reload {
+      match hot_reload::ShaderWatcher::new(shader::SceneSources::paths(config.shader)) {
+        Ok(watcher) => {
+          log::info!("[hot-reload]: watching {:?}", watcher.paths());
+          Some(watcher)
+        }
+        Err(e) => {
+          log::error!("[hot-reload]: couldn't watch shaders: {}", e);
+          None
+        }
+      }
+    } else {
+      None
+    };
+
+    Ok(Self {
+      shader: config.shader,
+      size: (width, height),
+      sources,
+      pipline,
+      mesh_data,
+      mesh,
+      camera,
+      camera_controller,
+      camera_binding,
+      last_update: None,
+      targets,
+      textured,
+      instanced,
+      particles,
+      post,
+      #[cfg(not(target_arch = "wasm32"))]
+      shader_watcher,
+    })
   }
 
-  let event_loop = EventLoop::new().unwrap();
-
-  let window = WindowBuilder::new().build(&event_loop).unwrap();
-  window.set_title("OpenGL Perf");
-  let _ = window.request_inner_size(PhysicalSize::new(800, 800));
-
-  // add canvas to the HTML document that we will host our application
-  #[cfg(target_arch = "wasm32")]
-  {
-    log::info!("[run]: initializing html canvas");
-    // Winit prevents sizing with CSS, so we have to set
-    // the size manually when on web.
-
-    use winit::platform::web::WindowExtWebSys;
-    web_sys::window()
-      .and_then(|win| win.document())
-      .and_then(|doc| {
-        let dst = doc.get_element_by_id("wgpu-container")?;
-        let canvas = window.canvas().unwrap();
-        let el = web_sys::HtmlCanvasElement::from(canvas);
-        dst.append_child(&el).ok()?;
-        Some(())
-      })
-      .expect("couldn't add canvas to document");
+  // Rebuilds the pipelines whose shader files on disk changed. Shaders that fail validation
+  // are reported and the last good pipeline stays in use.
+  #[cfg(not(target_arch = "wasm32"))]
+  fn reload_shaders(&mut self, device: &wgpu::Device) {
+    if !self.shader_watcher.as_ref().is_some_and(|watcher| watcher.changed()) {
+      return;
+    }
+
+    let sources = match shader::SceneSources::load(self.shader) {
+      Ok(sources) => sources,
+      Err(e) => {
+        log::error!("[hot-reload]: couldn't read shaders: {}", e);
+        return;
+      }
+    };
+    let targets = &self.targets;
+    let camera_layout = &self.camera_binding.bind_group_layout;
+
+    if let Some(pipline) = reload_source(&mut self.sources.triangle, sources.triangle, "triangle", |sources| {
+      triangle_pipeline(device, targets, camera_layout, sources)
+    }) {
+      self.pipline = pipline;
+    }
+
+    // checked even without a textured draw, so mistakes show up right away
+    let textured = reload_source(&mut self.sources.textured, sources.textured, "textured", |source| {
+      match &self.textured {
+        Some(textured) => textured_pipeline(device, targets, camera_layout, &textured.texture_layout, source),
+        None => textured_pipeline(device, targets, camera_layout, &Texture::bind_group_layout(device), source),
+      }
+    });
+    if let (Some(pipline), Some(textured)) = (textured, &mut self.textured) {
+      textured.pipline = pipline;
+    }
+
+    let instanced = reload_source(&mut self.sources.instanced, sources.instanced, "instanced", |source| {
+      instanced_pipeline(device, targets, camera_layout, source)
+    });
+    if let Some(pipline) = instanced {
+      // the particles are drawn with the same shader
+      if let Some(particles) = &mut self.particles {
+        match instanced_pipeline(device, targets, camera_layout, &self.sources.instanced) {
+          Ok(pipline) => particles.pipline = pipline,
+          Err(e) => log::error!("[hot-reload]: keeping the last good particle pipeline\n{}", e),
+        }
+      }
+      if let Some(instanced) = &mut self.instanced {
+        instanced.pipline = pipline;
+      }
+    }
   }
 
-  let app = app_surface::AppSurface::new(window).await;
-
-  let mut state = State::new(app);
-
-  let adapter_info = state.get_adapter_info();
-
-  log::info!("[run]: adapter_info {:?}", adapter_info);
-
-  event_loop.set_control_flow(ControlFlow::Wait);
-
-  let _ = event_loop.run(move |event, control_flow| {
-    match event {
-      Event::WindowEvent {
-        ref event,
-        window_id,
-      } if window_id == state.app.get_view().id() => {
-        match event {
-          WindowEvent::CloseRequested => control_flow.exit(),
-          #[allow(unused_variables)]
-          WindowEvent::Resized(new_size) => {
-            state.resize(&state.app.get_view().inner_size());
-            state.request_redraw();
-          },
-          WindowEvent::RedrawRequested => {
-            match state.render() {
-              Ok(_) => {}
-              // Reconfigure the surface is lost
-              Err(wgpu::SurfaceError::Lost) => log::error!("Surface is lost"),
-              // The system is out of memory
-              Err(wgpu::SurfaceError::OutOfMemory) => control_flow.exit(),
-              // others
-              Err(e) => log::error!("{:?}", e),
-            }
-            state.request_redraw();
-          }
-          _ => {}
+  // Creates every GPU object again on `gpu`, e.g. after the device was lost, keeping the camera,
+  // the instances, the shaders last reloaded and the post-processing params. The particles
+  // start over, their state only lived on the old device.
+  fn rebuild(&mut self, gpu: &Gpu) -> Result<(), DeviceError> {
+    let Gpu { device, queue, .. } = gpu;
+    self.mesh = self.mesh_data.upload(device, "Triangle");
+    self.camera_binding = CameraBinding::new(device, &CameraUniform::from_camera(&self.camera));
+    let (width, height) = self.size;
+    let targets = &self.targets;
+    self.targets = RenderTargets::new(device, width, height, targets.color_format(), targets.depth_format(), targets.sample_count());
+
+    let layout = &self.camera_binding.bind_group_layout;
+    let shaders = self.sources.triangle.create_modules(device);
+    self.pipline = create_pipeline(device, "Triangle", &self.targets, &shaders, &[Vertex::desc()], &[layout])?;
+
+    let sources = &self.sources;
+    self.textured = self.textured.take()
+      .and_then(|textured| textured.recreate(device, queue, &self.targets, layout, &sources.textured));
+    if let Some(instanced) = self.instanced.take() {
+      let mut instances = instanced.instances;
+      instances.discard_buffer();
+      match InstancedDraw::new(device, &self.targets, layout, &sources.instanced, instances) {
+        Ok(mut instanced) => {
+          instanced.instances.flush(device, queue);
+          self.instanced = Some(instanced);
         }
+        Err(e) => log::error!("[instances]: {}, drawing without instances", e),
       }
-      _ => (),
     }
-  });
+    self.particles = self.particles.take().and_then(|particles| {
+      ParticleDraw::new(device, queue, &self.targets, layout, &sources.instanced, particles.simulation.len() as u32)
+        .map_err(|e| log::error!("[particles]: {}, drawing without particles", e))
+        .ok()
+    });
+    self.post = self.post.take().and_then(|post| {
+      post.recreate(device)
+        .map_err(|e| log::error!("[post]: {}, drawing without post-processing", e))
+        .ok()
+    });
+    Ok(())
+  }
+
+  fn draws(&self) -> Vec<Draw<'_>> {
+    scene_draw(&self.pipline, &self.mesh, self.textured.as_ref(), self.instanced.as_ref())
+      .into_iter()
+      .chain(self.particles.as_ref().map(|particles| particles.draw(&self.mesh)))
+      .collect()
+  }
+}
+
+impl Scene for TriangleScene {
+  // Returns whether the camera controller used the event.
+  fn input(&mut self, event: &WindowEvent) -> bool {
+    self.camera_controller.process_event(event)
+  }
+
+  fn update(&mut self, gpu: &Gpu) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.reload_shaders(&gpu.device);
+
+    // capped, so a stall doesn't throw the camera or the particles far off
+    let now = Instant::now();
+    let dt = self.last_update.replace(now)
+      .map_or(Duration::ZERO, |last| now.saturating_duration_since(last))
+      .min(MAX_UPDATE_STEP);
+    self.camera.aspect = self.size.0 as f32 / self.size.1 as f32;
+    self.camera_controller.update_camera(&mut self.camera, dt);
+    self.camera_binding.write(&gpu.queue, &CameraUniform::from_camera(&self.camera));
+
+    if let Some(instanced) = &mut self.instanced {
+      instanced.instances.flush(&gpu.device, &gpu.queue);
+    }
+    if let Some(particles) = &mut self.particles {
+      particles.simulation.advance(&gpu.device, &gpu.queue, dt);
+    }
+  }
+
+  fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+    self.size = (width, height);
+    // the depth and multisampled targets have to match the surface they are drawn with
+    self.targets.resize(&gpu.device, width, height);
+    if let Some(post) = &mut self.post {
+      post.resize(&gpu.device, width, height);
+    }
+  }
+
+  fn recreate(&mut self, gpu: &Gpu) -> bool {
+    match self.rebuild(gpu) {
+      Ok(()) => true,
+      Err(e) => {
+        log::error!("[device]: {}, building the scene again", e);
+        false
+      }
+    }
+  }
+
+  fn render(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    encode_frame(encoder, frame, &self.targets, &self.camera_binding.bind_group, &self.draws(), self.post.as_ref());
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
+pub async fn run() {
+  renderer::init_logger();
+
+  run_with_config(Config::from_args().unwrap_or_else(|e| panic!("[config]: {e}"))).await
+}
+
+pub async fn run_with_config(config: Config) {
+  log::info!("[run]: {:?}", config);
+
+  let options = RunOptions {
+    title: "OpenGL Perf".to_string(),
+    size: cfg!(not(target_arch = "wasm32")).then(|| winit::dpi::PhysicalSize::new(800, 800).into()),
+    render_scale: config.render_scale,
+    present_mode: config.present_mode,
+    frame_latency: config.frame_latency,
+    target_fps: config.max_fps,
+    profile: true,
+    ..Default::default()
+  };
+  renderer::run(options, move |renderer| {
+    log::info!("[run]: adapter_info {:?}", renderer.gpu().adapter_info());
+    let (width, height) = renderer.size();
+    TriangleScene::new(renderer.gpu(), renderer.format(), width, height, &config)
+      .unwrap_or_else(|e| panic!("[run]: {e}"))
+  }).await;
 }
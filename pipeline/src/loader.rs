@@ -0,0 +1,228 @@
+use std::{fmt, path::Path};
+
+use glam::{Mat3, Mat4, Vec3};
+
+use crate::{Indices, MeshData, TexturedVertex, Vertex};
+
+// One mesh of a model file with everything the pipeline's `Vertex` can't carry kept alongside.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelMesh {
+  pub name: String,
+  pub positions: Vec<[f32; 3]>,
+  pub colors: Vec<[f32; 3]>,
+  pub normals: Option<Vec<[f32; 3]>>,
+  pub uvs: Option<Vec<[f32; 2]>>,
+  pub indices: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Model {
+  pub meshes: Vec<ModelMesh>,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+  UnsupportedFile(String),
+  Obj(tobj::LoadError),
+  Gltf(gltf::Error),
+}
+
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LoadError::UnsupportedFile(path) => write!(f, "{path} is not an .obj, .gltf or .glb file"),
+      LoadError::Obj(e) => write!(f, "failed to load obj: {e}"),
+      LoadError::Gltf(e) => write!(f, "failed to load gltf: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<tobj::LoadError> for LoadError {
+  fn from(e: tobj::LoadError) -> Self {
+    LoadError::Obj(e)
+  }
+}
+
+impl From<gltf::Error> for LoadError {
+  fn from(e: gltf::Error) -> Self {
+    LoadError::Gltf(e)
+  }
+}
+
+const WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+
+impl Model {
+  // Picks the format from the file extension.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+    let path = path.as_ref();
+    match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+      Some("obj") => Self::load_obj(path),
+      Some("gltf") | Some("glb") => Self::load_gltf(path),
+      _ => Err(LoadError::UnsupportedFile(path.display().to_string())),
+    }
+  }
+
+  pub fn load_obj(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+    let (models, materials) = tobj::load_obj(path.as_ref(), &tobj::LoadOptions {
+      single_index: true,
+      triangulate: true,
+      ..Default::default()
+    })?;
+    // a missing .mtl only costs the material colors
+    let materials = materials.unwrap_or_else(|e| {
+      log::warn!("[loader]: ignoring materials: {}", e);
+      Vec::new()
+    });
+
+    let meshes = models.into_iter().map(|model| {
+      let mesh = model.mesh;
+      let positions: Vec<[f32; 3]> = mesh.positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+
+      let colors = if mesh.vertex_color.len() == mesh.positions.len() {
+        mesh.vertex_color.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect()
+      } else {
+        let diffuse = mesh.material_id
+          .and_then(|id| materials.get(id))
+          .and_then(|material| material.diffuse)
+          .unwrap_or(WHITE);
+        vec![diffuse; positions.len()]
+      };
+
+      let normals = (!mesh.normals.is_empty())
+        .then(|| mesh.normals.chunks_exact(3).map(|n| [n[0], n[1], n[2]]).collect());
+      // OBJ puts v = 0 at the bottom, wgpu at the top
+      let uvs = (!mesh.texcoords.is_empty())
+        .then(|| mesh.texcoords.chunks_exact(2).map(|t| [t[0], 1.0 - t[1]]).collect());
+
+      ModelMesh { name: model.name, positions, colors, normals, uvs, indices: mesh.indices }
+    }).collect();
+
+    Ok(Self { meshes })
+  }
+
+  // Loads the default scene with node transforms applied. Only triangle list primitives are kept.
+  pub fn load_gltf(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+    let (document, buffers, _images) = gltf::import(path)?;
+
+    let mut model = Self::default();
+    if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+      for node in scene.nodes() {
+        model.add_gltf_node(&node, Mat4::IDENTITY, &buffers);
+      }
+    }
+    Ok(model)
+  }
+
+  fn add_gltf_node(&mut self, node: &gltf::Node, parent: Mat4, buffers: &[gltf::buffer::Data]) {
+    // column major, as glTF stores it
+    let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+    // ignores translation, and the inverse transpose keeps normals perpendicular under non-uniform scale
+    let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+    if let Some(mesh) = node.mesh() {
+      for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+          log::warn!("[loader]: skipping {:?} primitive of {:?}", primitive.mode(), mesh.name());
+          continue;
+        }
+
+        let reader = primitive.reader(|buffer| Some(buffers[buffer.index()].0.as_slice()));
+        let Some(positions) = reader.read_positions() else { continue };
+        let positions: Vec<[f32; 3]> = positions
+          .map(|p| transform.transform_point3(Vec3::from(p)).to_array())
+          .collect();
+
+        let colors = match reader.read_colors(0) {
+          Some(colors) => colors.into_rgb_f32().collect(),
+          None => {
+            let [r, g, b, _] = primitive.material().pbr_metallic_roughness().base_color_factor();
+            vec![[r, g, b]; positions.len()]
+          }
+        };
+        let normals = reader.read_normals()
+          .map(|normals| normals.map(|n| (normal_matrix * Vec3::from(n)).normalize_or_zero().to_array()).collect());
+        let uvs = reader.read_tex_coords(0).map(|uvs| uvs.into_f32().collect());
+        let indices = match reader.read_indices() {
+          Some(indices) => indices.into_u32().collect(),
+          None => (0..positions.len() as u32).collect(),
+        };
+
+        self.meshes.push(ModelMesh {
+          name: mesh.name().unwrap_or_default().to_string(),
+          positions,
+          colors,
+          normals,
+          uvs,
+          indices,
+        });
+      }
+    }
+
+    for child in node.children() {
+      self.add_gltf_node(&child, transform, buffers);
+    }
+  }
+
+  // Axis aligned (min, max) over all meshes, `None` for an empty model.
+  pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+    let mut positions = self.meshes.iter().flat_map(|mesh| mesh.positions.iter());
+    let first = *positions.next()?;
+    Some(positions.fold((first, first), |(mut min, mut max), p| {
+      for i in 0..3 {
+        min[i] = min[i].min(p[i]);
+        max[i] = max[i].max(p[i]);
+      }
+      (min, max)
+    }))
+  }
+
+  // Centers the model on the origin and scales it uniformly to fit the cube from -0.5 to 0.5,
+  // which the default camera frames whatever units the file was authored in.
+  pub fn fit_to_unit_cube(&mut self) {
+    let Some((min, max)) = self.bounds() else { return };
+    let center = [0, 1, 2].map(|i| (min[i] + max[i]) * 0.5);
+    let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0, f32::max);
+    if extent <= 0.0 {
+      return;
+    }
+
+    for mesh in &mut self.meshes {
+      for p in &mut mesh.positions {
+        *p = [0, 1, 2].map(|i| (p[i] - center[i]) / extent);
+      }
+    }
+  }
+
+  // Merges every mesh into one indexed mesh in the pipeline's vertex layout.
+  pub fn to_mesh_data(&self) -> MeshData<Vertex> {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for mesh in &self.meshes {
+      let base = vertices.len() as u32;
+      vertices.extend(mesh.positions.iter().zip(&mesh.colors).map(|(&position, &color)| Vertex { position, color }));
+      indices.extend(mesh.indices.iter().map(|index| base + index));
+    }
+
+    MeshData { vertices, indices: Some(Indices::new(indices)) }
+  }
+
+  // Like `to_mesh_data` but with texture coordinates, (0, 0) for meshes without any.
+  pub fn to_textured_mesh_data(&self) -> MeshData<TexturedVertex> {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for mesh in &self.meshes {
+      let base = vertices.len() as u32;
+      vertices.extend(mesh.positions.iter().enumerate().map(|(i, &position)| TexturedVertex {
+        position,
+        uv: mesh.uvs.as_ref().map_or([0.0; 2], |uvs| uvs[i]),
+      }));
+      indices.extend(mesh.indices.iter().map(|index| base + index));
+    }
+
+    MeshData { vertices, indices: Some(Indices::new(indices)) }
+  }
+}
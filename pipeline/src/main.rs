@@ -3,6 +3,14 @@ use pipeline::run;
 
 fn main() {
   #[cfg(not(target_arch = "wasm32"))] {
+    // pipeline --check-shaders [dirs...] validates shaders without opening a window or an adapter
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--check-shaders") {
+      let dirs: Vec<std::path::PathBuf> = args.iter().filter(|arg| !arg.starts_with("--")).map(Into::into).collect();
+      let dirs = if dirs.is_empty() { pipeline::shader_check::default_dirs() } else { dirs };
+      std::process::exit(if pipeline::shader_check::check(&dirs) { 0 } else { 1 });
+    }
+
     tokio::runtime::Runtime::new().unwrap().block_on(run());
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,170 @@
+use std::ops::Range;
+
+use wgpu::util::DeviceExt;
+
+use crate::{TexturedVertex, Vertex, VertexLayout};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+  U16(Vec<u16>),
+  U32(Vec<u32>),
+}
+
+impl Indices {
+  // Picks the smallest index format that can address every vertex.
+  pub fn new(indices: Vec<u32>) -> Self {
+    if indices.iter().all(|&index| index <= u16::MAX as u32) {
+      Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+    } else {
+      Indices::U32(indices)
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    match self {
+      Indices::U16(indices) => indices.len(),
+      Indices::U32(indices) => indices.len(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn format(&self) -> wgpu::IndexFormat {
+    match self {
+      Indices::U16(_) => wgpu::IndexFormat::Uint16,
+      Indices::U32(_) => wgpu::IndexFormat::Uint32,
+    }
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    match self {
+      Indices::U16(indices) => bytemuck::cast_slice(indices),
+      Indices::U32(indices) => bytemuck::cast_slice(indices),
+    }
+  }
+}
+
+// Geometry on the CPU side, ready to be uploaded as a `Mesh`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshData<V> {
+  pub vertices: Vec<V>,
+  pub indices: Option<Indices>,
+}
+
+impl<V: VertexLayout> MeshData<V> {
+  pub fn upload(&self, device: &wgpu::Device, label: &str) -> Mesh {
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some(&format!("{label} Vertex Buffer")),
+      contents: V::as_bytes(&self.vertices),
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    let index_buffer = self.indices.as_ref().map(|indices| IndexBuffer {
+      buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{label} Index Buffer")),
+        contents: indices.as_bytes(),
+        usage: wgpu::BufferUsages::INDEX,
+      }),
+      format: indices.format(),
+      num_indices: indices.len() as u32,
+    });
+
+    Mesh { vertex_buffer, num_vertices: self.vertices.len() as u32, index_buffer }
+  }
+}
+
+struct IndexBuffer {
+  buffer: wgpu::Buffer,
+  format: wgpu::IndexFormat,
+  num_indices: u32,
+}
+
+// Vertex and optional index buffer on the GPU.
+pub struct Mesh {
+  vertex_buffer: wgpu::Buffer,
+  num_vertices: u32,
+  index_buffer: Option<IndexBuffer>,
+}
+
+impl Mesh {
+  pub fn num_vertices(&self) -> u32 {
+    self.num_vertices
+  }
+
+  pub fn num_indices(&self) -> Option<u32> {
+    self.index_buffer.as_ref().map(|index_buffer| index_buffer.num_indices)
+  }
+
+  // Binds the buffers to vertex buffer slot 0 and draws with `draw_indexed` when there are indices.
+  pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    match &self.index_buffer {
+      Some(index_buffer) => {
+        render_pass.set_index_buffer(index_buffer.buffer.slice(..), index_buffer.format);
+        render_pass.draw_indexed(0..index_buffer.num_indices, 0, instances);
+      }
+      None => render_pass.draw(0..self.num_vertices, instances),
+    }
+  }
+}
+
+// Primitives in clip space, counter-clockwise like the pipeline expects.
+impl MeshData<Vertex> {
+  pub fn triangle() -> Self {
+    Self {
+      vertices: vec![
+        Vertex { position: [ 0.00, 0.50, 0.00], color: [1.0, 0.0, 0.0] },
+        Vertex { position: [-0.58,-0.50, 0.00], color: [0.0, 1.0, 0.0] },
+        Vertex { position: [ 0.58,-0.50, 0.00], color: [0.0, 0.0, 1.0] },
+      ],
+      indices: Some(Indices::U16(vec![0, 1, 2])),
+    }
+  }
+
+  pub fn quad() -> Self {
+    Self {
+      vertices: vec![
+        Vertex { position: [-0.50,-0.50, 0.00], color: [1.0, 0.0, 0.0] },
+        Vertex { position: [ 0.50,-0.50, 0.00], color: [0.0, 1.0, 0.0] },
+        Vertex { position: [ 0.50, 0.50, 0.00], color: [0.0, 0.0, 1.0] },
+        Vertex { position: [-0.50, 0.50, 0.00], color: [1.0, 1.0, 0.0] },
+      ],
+      indices: Some(Indices::U16(vec![0, 1, 2, 0, 2, 3])),
+    }
+  }
+
+  // A regular polygon with `sides` corners around a white center, as a triangle fan.
+  pub fn polygon(sides: u32, radius: f32) -> Self {
+    let sides = sides.max(3);
+    let mut vertices = vec![Vertex { position: [0.0, 0.0, 0.0], color: [1.0, 1.0, 1.0] }];
+    for i in 0..sides {
+      let angle = std::f32::consts::TAU * i as f32 / sides as f32;
+      let (sin, cos) = angle.sin_cos();
+      vertices.push(Vertex {
+        position: [radius * cos, radius * sin, 0.0],
+        color: [0.5 + 0.5 * cos, 0.5 + 0.5 * sin, 0.5],
+      });
+    }
+
+    let indices = (0..sides).flat_map(|i| [0, i + 1, (i + 1) % sides + 1]).collect();
+
+    Self { vertices, indices: Some(Indices::new(indices)) }
+  }
+}
+
+impl MeshData<TexturedVertex> {
+  // The colored quad's corners with the whole texture stretched over them.
+  pub fn textured_quad() -> Self {
+    Self {
+      vertices: vec![
+        TexturedVertex { position: [-0.50,-0.50, 0.00], uv: [0.0, 1.0] },
+        TexturedVertex { position: [ 0.50,-0.50, 0.00], uv: [1.0, 1.0] },
+        TexturedVertex { position: [ 0.50, 0.50, 0.00], uv: [1.0, 0.0] },
+        TexturedVertex { position: [-0.50, 0.50, 0.00], uv: [0.0, 0.0] },
+      ],
+      indices: Some(Indices::U16(vec![0, 1, 2, 0, 2, 3])),
+    }
+  }
+}
@@ -0,0 +1,25 @@
+// 生成 mipmap：把上一级纹理画到下一级
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) uv: vec2f,
+};
+
+// 一个覆盖全屏的三角形
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.uv = uv;
+  out.clip_position = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+  return out;
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    return textureSample(source, source_sampler, in.uv);
+}
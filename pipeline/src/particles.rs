@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use wgpu::util::DeviceExt;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::compute::ComputeError;
+use crate::{compute::{ComputeKernel, StorageBuffer}, Instance};
+
+// Matches `Particle` in `particles.wgsl`, where vec3f is 16 byte aligned.
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct Particle {
+  pub position: [f32; 3],
+  pub radius: f32,
+  pub velocity: [f32; 3],
+  // seconds simulated so far
+  pub age: f32,
+}
+
+const SLOW_COLOR: [f32; 4] = [0.2, 0.4, 1.0, 1.0];
+const FAST_COLOR: [f32; 4] = [1.0, 0.5, 0.1, 1.0];
+const FAST_SPEED: f32 = 4.0;
+
+impl Particle {
+  pub fn new(position: [f32; 3], velocity: [f32; 3], radius: f32) -> Self {
+    Self { position, radius, velocity, age: 0.0 }
+  }
+
+  // What `particles.wgsl` does to one particle, for checking the GPU results.
+  pub fn step(&mut self, params: &SimParams) {
+    for axis in 0..3 {
+      self.velocity[axis] += params.gravity[axis] * params.dt;
+      self.position[axis] += self.velocity[axis] * params.dt;
+
+      let low = params.bounds_min[axis] + self.radius;
+      let high = params.bounds_max[axis] - self.radius;
+      if self.position[axis] < low {
+        self.velocity[axis] = self.velocity[axis].abs() * params.restitution;
+      }
+      if self.position[axis] > high {
+        self.velocity[axis] = -self.velocity[axis].abs() * params.restitution;
+      }
+      self.position[axis] = self.position[axis].clamp(low, high);
+    }
+    self.age += params.dt;
+  }
+
+  // The instance the kernel writes for this particle: a sphere of `radius`, tinted from
+  // blue to orange as it speeds up.
+  pub fn instance(&self) -> Instance {
+    let speed = self.velocity.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let t = (speed / FAST_SPEED).clamp(0.0, 1.0);
+    let color = std::array::from_fn(|i| SLOW_COLOR[i] + (FAST_COLOR[i] - SLOW_COLOR[i]) * t);
+    Instance::new(self.position, self.radius, color)
+  }
+}
+
+// Per-step constants of the simulation. Particles bounce off the inside of the box
+// between `bounds_min` and `bounds_max`, keeping `restitution` of their speed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SimParams {
+  pub gravity: [f32; 3],
+  pub dt: f32,
+  pub bounds_min: [f32; 3],
+  pub bounds_max: [f32; 3],
+  pub restitution: f32,
+}
+
+impl Default for SimParams {
+  fn default() -> Self {
+    Self {
+      gravity: [0.0, -9.8, 0.0],
+      dt: 1.0 / 60.0,
+      bounds_min: [-1.0; 3],
+      bounds_max: [1.0; 3],
+      restitution: 0.8,
+    }
+  }
+}
+
+// `SimParams` laid out like the uniform in `particles.wgsl`.
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ParamsUniform {
+  gravity: [f32; 3],
+  dt: f32,
+  bounds_min: [f32; 3],
+  restitution: f32,
+  bounds_max: [f32; 3],
+  count: u32,
+}
+
+impl ParamsUniform {
+  fn new(params: &SimParams, count: u32) -> Self {
+    Self {
+      gravity: params.gravity,
+      dt: params.dt,
+      bounds_min: params.bounds_min,
+      restitution: params.restitution,
+      bounds_max: params.bounds_max,
+      count,
+    }
+  }
+}
+
+// `count` particles spread over a disc above the origin, thrown up and outwards.
+pub fn particle_fountain(count: u32) -> Vec<Particle> {
+  // the golden angle spreads the particles evenly without any randomness
+  let golden_angle = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+  (0..count).map(|i| {
+    let angle = i as f32 * golden_angle;
+    let r = ((i as f32 + 0.5) / count as f32).sqrt() * 0.5;
+    let (sin, cos) = angle.sin_cos();
+    let lift = (i as f32 * 0.618_034).fract();
+    Particle::new([r * cos, 0.5, r * sin], [cos * 1.5, 1.0 + 3.0 * lift, sin * 1.5], 0.03)
+  }).collect()
+}
+
+// Particles stepped by `particles.wgsl`. Each step also writes an `Instance` per particle
+// into `instance_buffer`, which the instanced pipeline draws from vertex buffer slot 1.
+pub struct ParticleSimulation {
+  kernel: ComputeKernel,
+  params: SimParams,
+  params_buffer: wgpu::Buffer,
+  particles: StorageBuffer<Particle>,
+  instances: StorageBuffer<Instance>,
+  bind_group: wgpu::BindGroup,
+  // the time `advance` was given that is shorter than a step, carried over to the next call
+  pending: Duration,
+}
+
+impl ParticleSimulation {
+  pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, params: SimParams, particles: &[Particle]) -> Self {
+    let kernel = ComputeKernel::new(device, "particles.wgsl", include_str!("particles.wgsl"), "cs_main");
+
+    let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Particle Params Buffer"),
+      contents: bytemuck::bytes_of(&ParamsUniform::new(&params, particles.len() as u32)),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let instances: Vec<_> = particles.iter().map(Particle::instance).collect();
+    let particles = StorageBuffer::with_data(device, queue, "Particle Buffer", particles, wgpu::BufferUsages::empty());
+    let instances = StorageBuffer::with_data(device, queue, "Particle Instance Buffer", &instances, wgpu::BufferUsages::VERTEX);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Particle Bind Group"),
+      layout: &kernel.bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: params_buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: particles.binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 2,
+          resource: instances.binding(),
+        },
+      ],
+    });
+
+    Self { kernel, params, params_buffer, particles, instances, bind_group, pending: Duration::ZERO }
+  }
+
+  pub fn len(&self) -> usize {
+    self.particles.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.particles.is_empty()
+  }
+
+  pub fn params(&self) -> &SimParams {
+    &self.params
+  }
+
+  pub fn set_params(&mut self, queue: &wgpu::Queue, params: SimParams) {
+    self.params = params;
+    let uniform = ParamsUniform::new(&params, self.len() as u32);
+    queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&uniform));
+  }
+
+  // Records `steps` steps, each in its own pass so every step sees the one before.
+  pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+    for _ in 0..steps {
+      self.kernel.dispatch(encoder, &self.bind_group, self.len() as u32);
+    }
+  }
+
+  pub fn step(&self, device: &wgpu::Device, queue: &wgpu::Queue, steps: u32) {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("Particle Encoder"),
+    });
+    self.encode(&mut encoder, steps);
+    queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  // Runs as many whole steps of `SimParams::dt` as fit into `elapsed` and what was left over
+  // the last time, so the particles move at the same speed whatever the frame rate. Returns
+  // the number of steps.
+  pub fn advance(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, elapsed: Duration) -> u32 {
+    let Ok(dt) = Duration::try_from_secs_f32(self.params.dt) else {
+      return 0;
+    };
+    if dt.is_zero() {
+      return 0;
+    }
+    self.pending += elapsed;
+    let steps = (self.pending.as_nanos() / dt.as_nanos()) as u32;
+    self.pending -= dt * steps;
+    if steps > 0 {
+      self.step(device, queue, steps);
+    }
+    steps
+  }
+
+  // One `Instance` per particle, usable as an instance vertex buffer.
+  pub fn instance_buffer(&self) -> &wgpu::Buffer {
+    self.instances.buffer()
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn read_particles(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<Particle>, ComputeError> {
+    self.particles.read(device, queue)
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn read_instances(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<Instance>, ComputeError> {
+    self.instances.read(device, queue)
+  }
+}
+
+// Runs `steps` steps on a headless device and reads the particles back, for testing
+// the simulation without a window.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn simulate_headless(params: SimParams, particles: &[Particle], steps: u32) -> Result<Vec<Particle>, ComputeError> {
+  let compute = crate::compute::HeadlessCompute::new().await?;
+  let simulation = ParticleSimulation::new(&compute.device, &compute.queue, params, particles);
+  simulation.step(&compute.device, &compute.queue, steps);
+  simulation.read_particles(&compute.device, &compute.queue)
+}
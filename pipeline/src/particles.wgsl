@@ -0,0 +1,64 @@
+// 粒子模拟：每个线程处理一个粒子
+// WebGL2 没有计算着色器，只在支持的平台上使用
+// check-shaders: skip WebGL2
+struct Particle {
+    position: vec3f,
+    radius: f32,
+    velocity: vec3f,
+    age: f32,
+};
+
+struct SimParams {
+    gravity: vec3f,
+    dt: f32,
+    bounds_min: vec3f,
+    restitution: f32,
+    bounds_max: vec3f,
+    count: u32,
+};
+
+// 和 instanced.wgsl 读取的实例属性布局相同
+struct Instance {
+    position: vec3f,
+    scale: f32,
+    rotation: vec4f,
+    color: vec4f,
+};
+
+@group(0) @binding(0)
+var<uniform> params: SimParams;
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2)
+var<storage, read_write> instances: array<Instance>;
+
+const SLOW_COLOR = vec4f(0.2, 0.4, 1.0, 1.0);
+const FAST_COLOR = vec4f(1.0, 0.5, 0.1, 1.0);
+const FAST_SPEED = 4.0;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3u) {
+  let index = id.x;
+  if (index >= params.count) {
+    return;
+  }
+
+  var p = particles[index];
+  p.velocity += params.gravity * params.dt;
+  p.position += p.velocity * params.dt;
+  p.age += params.dt;
+
+  // 碰到边界就反弹
+  let low = params.bounds_min + vec3f(p.radius);
+  let high = params.bounds_max - vec3f(p.radius);
+  let below = p.position < low;
+  let above = p.position > high;
+  p.position = clamp(p.position, low, high);
+  p.velocity = select(p.velocity, abs(p.velocity) * params.restitution, below);
+  p.velocity = select(p.velocity, -abs(p.velocity) * params.restitution, above);
+
+  particles[index] = p;
+
+  let t = clamp(length(p.velocity) / FAST_SPEED, 0.0, 1.0);
+  instances[index] = Instance(p.position, p.radius, vec4f(0.0, 0.0, 0.0, 1.0), mix(SLOW_COLOR, FAST_COLOR, t));
+}
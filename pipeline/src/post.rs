@@ -0,0 +1,376 @@
+use std::{cell::Cell, collections::HashMap, fmt};
+
+use renderer::{Frame, Gpu, GpuTimer, RenderNode};
+use wgpu::util::DeviceExt;
+
+use crate::{config::Effect, reflect, shader};
+
+// What the scene pass draws into, readable by the first pass.
+pub const SCENE: &str = "scene";
+// The view handed to `RenderGraph::encode`, e.g. the surface texture.
+pub const OUTPUT: &str = "output";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+  // the input is neither `SCENE` nor written by an earlier pass
+  UnknownInput { pass: String, input: String },
+  // a pass can't sample the texture it draws into
+  Feedback { pass: String, target: String },
+  Shader { pass: String, error: String },
+}
+
+impl fmt::Display for GraphError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GraphError::UnknownInput { pass, input } => write!(f, "{pass} reads `{input}` before any pass writes it"),
+      GraphError::Feedback { pass, target } => write!(f, "{pass} reads and writes `{target}`"),
+      GraphError::Shader { pass, error } => write!(f, "{pass}: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for GraphError {}
+
+// A pass that draws one fullscreen triangle into `output`, sampling `inputs`.
+//
+// `shader` only needs a `fs_main` taking the uv at `@location(0)`, the vertex stage comes
+// from `fullscreen.wgsl`. It sees the sampler at `@binding(0)`, the inputs from
+// `@binding(1)` on and `params`, if any, as a uniform after the last input, all in group 0.
+pub struct FullscreenPass<'a> {
+  pub label: &'a str,
+  pub shader: &'a str,
+  pub inputs: &'a [&'a str],
+  pub output: &'a str,
+  pub params: Option<[f32; 4]>,
+}
+
+struct Target {
+  _texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+// The uniform of a pass and what was last written to it.
+struct Params {
+  buffer: wgpu::Buffer,
+  values: Cell<[f32; 4]>,
+}
+
+// Keeps what it was added with, so `RenderGraph::recreate` can add it again.
+struct Pass {
+  label: String,
+  shader: String,
+  inputs: Vec<String>,
+  output: String,
+  pipeline: wgpu::RenderPipeline,
+  params: Option<Params>,
+  // refers to the targets, so it is rebuilt when they are
+  bind_group: wgpu::BindGroup,
+}
+
+// Fullscreen passes run in the order they were added, after the scene is drawn into
+// `SCENE`. Every texture a pass writes other than `OUTPUT` is allocated here at the size
+// of the graph and reallocated by `resize`, so passes only refer to them by name.
+pub struct RenderGraph {
+  format: wgpu::TextureFormat,
+  target_format: wgpu::TextureFormat,
+  width: u32,
+  height: u32,
+  sampler: wgpu::Sampler,
+  targets: HashMap<String, Target>,
+  passes: Vec<Pass>,
+}
+
+impl RenderGraph {
+  // `format` is used for every target and has to be the format of the `OUTPUT` view.
+  pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+    Self::with_target_format(device, format, format, width, height)
+  }
+
+  // `format` is the format of the `OUTPUT` view, `target_format` that of `SCENE` and the
+  // textures between the passes, e.g. `Rgba16Float` to keep values above 1 for a tonemap.
+  pub fn with_target_format(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    target_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+  ) -> Self {
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("Post Sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let mut graph = Self { format, target_format, width, height, sampler, targets: HashMap::new(), passes: Vec::new() };
+    graph.allocate(device, SCENE.to_string());
+    graph
+  }
+
+  // The graph for `effects` applied one after the other, the last one drawing into `OUTPUT`.
+  pub fn post_process(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    effects: &[Effect],
+  ) -> Result<Self, GraphError> {
+    // the tonemap has to see the range above 1, an 8 bit target would clip it
+    let target_format = if effects.contains(&Effect::Tonemap) { wgpu::TextureFormat::Rgba16Float } else { format };
+    let mut graph = Self::with_target_format(device, format, target_format, width, height);
+
+    let mut input = SCENE.to_string();
+    for (i, effect) in effects.iter().enumerate() {
+      let output = if i + 1 == effects.len() { OUTPUT.to_string() } else { format!("{effect}{i}") };
+      let label = format!("{effect}{i}");
+      // (label, shader, params, output)
+      let passes = match effect {
+        Effect::Grayscale => vec![(label, include_str!("grayscale.wgsl"), None, output)],
+        // separable, so one horizontal and one vertical pass of 9 taps each
+        Effect::Blur => vec![
+          (format!("{label}_horizontal"), include_str!("blur.wgsl"), Some([1.0, 0.0, 0.0, 0.0]), format!("{label}_horizontal")),
+          (format!("{label}_vertical"), include_str!("blur.wgsl"), Some([0.0, 1.0, 0.0, 0.0]), output),
+        ],
+        // x is the exposure
+        Effect::Tonemap => vec![(label, include_str!("tonemap.wgsl"), Some([1.0, 0.0, 0.0, 0.0]), output)],
+      };
+
+      for (label, shader, params, pass_output) in passes {
+        graph.add_pass(device, &FullscreenPass {
+          label: &label,
+          shader,
+          inputs: &[&input],
+          output: &pass_output,
+          params,
+        })?;
+        input = pass_output;
+      }
+    }
+    Ok(graph)
+  }
+
+  pub fn add_pass(&mut self, device: &wgpu::Device, desc: &FullscreenPass) -> Result<(), GraphError> {
+    let pass = desc.label.to_string();
+    for &input in desc.inputs {
+      if input == desc.output {
+        return Err(GraphError::Feedback { pass, target: input.to_string() });
+      }
+      if !self.targets.contains_key(input) {
+        return Err(GraphError::UnknownInput { pass, input: input.to_string() });
+      }
+    }
+    if desc.output == SCENE {
+      return Err(GraphError::Feedback { pass, target: SCENE.to_string() });
+    }
+
+    let source = format!("{}\n{}", include_str!("fullscreen.wgsl"), desc.shader);
+    let module = reflect::parse_wgsl(&source).map_err(|e| GraphError::Shader { pass: pass.clone(), error: e.to_string() })?;
+    shader::validate_module(&module, &source, desc.label)
+      .map_err(|e| GraphError::Shader { pass: pass.clone(), error: e.to_string() })?;
+
+    // naga accepted the shader, but wgpu can still reject the pipeline
+    let output_format = if desc.output == OUTPUT { self.format } else { self.target_format };
+    let pipeline = renderer::validate(device, &format!("{} Post Pipeline", desc.label), || {
+      let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(desc.label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+      });
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("{} Post Pipeline", desc.label)),
+        layout: None,
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(output_format.into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+      })
+    }).map_err(|e| GraphError::Shader { pass: pass.clone(), error: e.to_string() })?;
+    let params = desc.params.map(|values| Params {
+      buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{} Params Buffer", desc.label)),
+        contents: bytemuck::bytes_of(&values),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      }),
+      values: Cell::new(values),
+    });
+
+    if desc.output != OUTPUT && !self.targets.contains_key(desc.output) {
+      self.allocate(device, desc.output.to_string());
+    }
+
+    let inputs: Vec<String> = desc.inputs.iter().map(|input| input.to_string()).collect();
+    let bind_group = self.bind_group(device, &pass, &pipeline, &inputs, params.as_ref().map(|params| &params.buffer));
+    self.passes.push(Pass {
+      label: pass,
+      shader: desc.shader.to_string(),
+      inputs,
+      output: desc.output.to_string(),
+      pipeline,
+      params,
+      bind_group,
+    });
+    Ok(())
+  }
+
+  fn allocate(&mut self, device: &wgpu::Device, name: String) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(&name),
+      size: wgpu::Extent3d {
+        width: self.width.max(1),
+        height: self.height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: self.target_format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    self.targets.insert(name, Target { _texture: texture, view });
+  }
+
+  fn bind_group(
+    &self,
+    device: &wgpu::Device,
+    label: &str,
+    pipeline: &wgpu::RenderPipeline,
+    inputs: &[String],
+    params: Option<&wgpu::Buffer>,
+  ) -> wgpu::BindGroup {
+    let mut entries = vec![wgpu::BindGroupEntry {
+      binding: 0,
+      resource: wgpu::BindingResource::Sampler(&self.sampler),
+    }];
+    for (i, input) in inputs.iter().enumerate() {
+      entries.push(wgpu::BindGroupEntry {
+        binding: i as u32 + 1,
+        resource: wgpu::BindingResource::TextureView(&self.targets[input].view),
+      });
+    }
+    if let Some(params) = params {
+      entries.push(wgpu::BindGroupEntry {
+        binding: inputs.len() as u32 + 1,
+        resource: params.as_entire_binding(),
+      });
+    }
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some(&format!("{label} Post Bind Group")),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &entries,
+    })
+  }
+
+  pub fn format(&self) -> wgpu::TextureFormat {
+    self.format
+  }
+
+  // The format the scene has to be drawn in, see `scene_view`.
+  pub fn target_format(&self) -> wgpu::TextureFormat {
+    self.target_format
+  }
+
+  // Where the scene has to be drawn before `encode`.
+  pub fn scene_view(&self) -> &wgpu::TextureView {
+    &self.targets[SCENE].view
+  }
+
+  pub fn view(&self, name: &str) -> Option<&wgpu::TextureView> {
+    self.targets.get(name).map(|target| &target.view)
+  }
+
+  // Overwrites the params of the pass called `label`, e.g. the exposure of "tonemap0".
+  // Returns false if there is no such pass or it has no params.
+  pub fn set_params(&self, queue: &wgpu::Queue, label: &str, params: [f32; 4]) -> bool {
+    let Some(pass_params) = self.passes.iter().find(|pass| pass.label == label).and_then(|pass| pass.params.as_ref()) else {
+      return false;
+    };
+    queue.write_buffer(&pass_params.buffer, 0, bytemuck::bytes_of(&params));
+    pass_params.values.set(params);
+    true
+  }
+
+  // The same passes on another device, e.g. after the one the graph was created on was lost,
+  // with the params they were last given.
+  pub fn recreate(&self, device: &wgpu::Device) -> Result<Self, GraphError> {
+    let mut graph = Self::with_target_format(device, self.format, self.target_format, self.width, self.height);
+    for pass in &self.passes {
+      let inputs: Vec<&str> = pass.inputs.iter().map(String::as_str).collect();
+      graph.add_pass(device, &FullscreenPass {
+        label: &pass.label,
+        shader: &pass.shader,
+        inputs: &inputs,
+        output: &pass.output,
+        params: pass.params.as_ref().map(|params| params.values.get()),
+      })?;
+    }
+    Ok(graph)
+  }
+
+  // Reallocates the targets if the size changed.
+  pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    if (self.width, self.height) == (width, height) {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+
+    let names: Vec<String> = self.targets.keys().cloned().collect();
+    for name in names {
+      self.allocate(device, name);
+    }
+    let bind_groups: Vec<_> = self.passes.iter()
+      .map(|pass| {
+        self.bind_group(device, &pass.label, &pass.pipeline, &pass.inputs, pass.params.as_ref().map(|params| &params.buffer))
+      })
+      .collect();
+    for (pass, bind_group) in self.passes.iter_mut().zip(bind_groups) {
+      pass.bind_group = bind_group;
+    }
+  }
+
+  // Each pass is timed under its label while `timer` is recording.
+  pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView, timer: Option<&GpuTimer>) {
+    for pass in &self.passes {
+      let view = if pass.output == OUTPUT { output } else { &self.targets[&pass.output].view };
+      let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(&pass.label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        timestamp_writes: timer.and_then(|timer| timer.render_pass_writes(&pass.label)),
+        ..Default::default()
+      });
+      render_pass.set_pipeline(&pass.pipeline);
+      render_pass.set_bind_group(0, &pass.bind_group, &[]);
+      render_pass.draw(0..3, 0..1);
+    }
+  }
+}
+
+// Lets the graph run as the last node of a scene, drawing into the frame as `OUTPUT`.
+impl RenderNode for RenderGraph {
+  fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+    RenderGraph::resize(self, &gpu.device, width, height);
+  }
+
+  fn encode(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    RenderGraph::encode(self, encoder, frame.view, frame.timer);
+  }
+}
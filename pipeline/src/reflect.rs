@@ -0,0 +1,245 @@
+use std::fmt;
+
+use crate::VertexLayout;
+
+// A `@location` input of a vertex entry point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexInput {
+  pub name: String,
+  pub location: u32,
+  pub format: wgpu::VertexFormat,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+  Parse(String),
+  MissingEntryPoint(String),
+  NotAVertexEntryPoint(String),
+  UnsupportedInput { name: String, location: u32 },
+  Mismatch(Vec<LayoutMismatch>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutMismatch {
+  // the shader reads a location no vertex buffer provides
+  MissingLocation { location: u32, name: String, format: wgpu::VertexFormat },
+  // a vertex buffer provides a location the shader doesn't read
+  UnusedLocation { location: u32, format: wgpu::VertexFormat },
+  Format { location: u32, name: String, shader: wgpu::VertexFormat, buffer: wgpu::VertexFormat },
+}
+
+impl fmt::Display for LayoutMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LayoutMismatch::MissingLocation { location, name, format } =>
+        write!(f, "@location({location}) {name}: {format:?} is not provided by any vertex buffer"),
+      LayoutMismatch::UnusedLocation { location, format } =>
+        write!(f, "@location({location}) {format:?} is provided but not read by the shader"),
+      LayoutMismatch::Format { location, name, shader, buffer } =>
+        write!(f, "@location({location}) {name}: shader expects {shader:?} but the buffer provides {buffer:?}"),
+    }
+  }
+}
+
+impl fmt::Display for ReflectError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReflectError::Parse(e) => write!(f, "{e}"),
+      ReflectError::MissingEntryPoint(name) => write!(f, "no entry point named `{name}`"),
+      ReflectError::NotAVertexEntryPoint(name) => write!(f, "`{name}` is not a vertex entry point"),
+      ReflectError::UnsupportedInput { name, location } =>
+        write!(f, "@location({location}) {name} has a type that can't be a vertex attribute"),
+      ReflectError::Mismatch(mismatches) => {
+        for mismatch in mismatches {
+          writeln!(f, "{mismatch}")?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+impl std::error::Error for ReflectError {}
+
+pub fn parse_wgsl(source: &str) -> Result<naga::Module, ReflectError> {
+  naga::front::wgsl::parse_str(source).map_err(|e| ReflectError::Parse(e.emit_to_string(source)))
+}
+
+// GLSL shaders hold a single stage, their entry point is always `main`.
+pub fn parse_glsl(source: &str, stage: naga::ShaderStage) -> Result<naga::Module, ReflectError> {
+  naga::front::glsl::Frontend::default()
+    .parse(&naga::front::glsl::Options::from(stage), source)
+    .map_err(|errors| ReflectError::Parse(
+      errors.into_iter()
+        .map(|e| naga::WithSpan::new(e.kind).with_span(e.meta, "").emit_to_string(source))
+        .collect()
+    ))
+}
+
+// Collects the `@location` inputs of a vertex entry point, sorted by location.
+// Inputs can be plain arguments or members of a struct argument.
+pub fn vertex_inputs(module: &naga::Module, entry_point: &str) -> Result<Vec<VertexInput>, ReflectError> {
+  let entry = module.entry_points.iter()
+    .find(|ep| ep.name == entry_point)
+    .ok_or_else(|| ReflectError::MissingEntryPoint(entry_point.to_string()))?;
+
+  if entry.stage != naga::ShaderStage::Vertex {
+    return Err(ReflectError::NotAVertexEntryPoint(entry_point.to_string()));
+  }
+
+  let mut inputs = Vec::new();
+  for argument in &entry.function.arguments {
+    let name = argument.name.clone().unwrap_or_default();
+    match (&argument.binding, &module.types[argument.ty].inner) {
+      (Some(binding), inner) => {
+        if let Some(input) = vertex_input(name, binding, inner)? {
+          inputs.push(input);
+        }
+      }
+      (None, naga::TypeInner::Struct { members, .. }) => {
+        for member in members {
+          let name = format!("{}.{}", name, member.name.as_deref().unwrap_or_default());
+          if let Some(binding) = &member.binding {
+            if let Some(input) = vertex_input(name, binding, &module.types[member.ty].inner)? {
+              inputs.push(input);
+            }
+          }
+        }
+      }
+      (None, _) => {}
+    }
+  }
+
+  inputs.sort_by_key(|input| input.location);
+  Ok(inputs)
+}
+
+fn vertex_input(name: String, binding: &naga::Binding, inner: &naga::TypeInner) -> Result<Option<VertexInput>, ReflectError> {
+  let location = match binding {
+    naga::Binding::Location { location, .. } => *location,
+    naga::Binding::BuiltIn(_) => return Ok(None),
+  };
+  match shader_format(inner) {
+    Some(format) => Ok(Some(VertexInput { name, location, format })),
+    None => Err(ReflectError::UnsupportedInput { name, location }),
+  }
+}
+
+fn shader_format(inner: &naga::TypeInner) -> Option<wgpu::VertexFormat> {
+  use naga::{ScalarKind::*, VectorSize::*};
+  use wgpu::VertexFormat::*;
+
+  let format = match *inner {
+    naga::TypeInner::Scalar(scalar) => match (scalar.kind, scalar.width) {
+      (Float, 4) => Float32,
+      (Float, 8) => Float64,
+      (Sint, 4) => Sint32,
+      (Uint, 4) => Uint32,
+      _ => return None,
+    },
+    naga::TypeInner::Vector { size, scalar } => match (scalar.kind, scalar.width, size) {
+      (Float, 4, Bi) => Float32x2,
+      (Float, 4, Tri) => Float32x3,
+      (Float, 4, Quad) => Float32x4,
+      (Float, 8, Bi) => Float64x2,
+      (Float, 8, Tri) => Float64x3,
+      (Float, 8, Quad) => Float64x4,
+      (Sint, 4, Bi) => Sint32x2,
+      (Sint, 4, Tri) => Sint32x3,
+      (Sint, 4, Quad) => Sint32x4,
+      (Uint, 4, Bi) => Uint32x2,
+      (Uint, 4, Tri) => Uint32x3,
+      (Uint, 4, Quad) => Uint32x4,
+      _ => return None,
+    },
+    _ => return None,
+  };
+  Some(format)
+}
+
+// The type a buffer format arrives as in the shader, e.g. `Unorm8x4` is read as `vec4f`.
+fn shader_visible_format(format: wgpu::VertexFormat) -> wgpu::VertexFormat {
+  use wgpu::VertexFormat::*;
+
+  match format {
+    Unorm8x2 | Snorm8x2 | Unorm16x2 | Snorm16x2 | Float16x2 => Float32x2,
+    Unorm8x4 | Snorm8x4 | Unorm16x4 | Snorm16x4 | Float16x4 => Float32x4,
+    Uint8x2 | Uint16x2 => Uint32x2,
+    Uint8x4 | Uint16x4 => Uint32x4,
+    Sint8x2 | Sint16x2 => Sint32x2,
+    Sint8x4 | Sint16x4 => Sint32x4,
+    other => other,
+  }
+}
+
+// A tightly packed vertex buffer layout generated from shader inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReflectedLayout {
+  pub array_stride: wgpu::BufferAddress,
+  pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl ReflectedLayout {
+  pub fn from_inputs(inputs: &[VertexInput]) -> Self {
+    let mut array_stride = 0;
+    let attributes = inputs.iter().map(|input| {
+      let attribute = wgpu::VertexAttribute {
+        offset: array_stride,
+        shader_location: input.location,
+        format: input.format,
+      };
+      array_stride += input.format.size();
+      attribute
+    }).collect();
+
+    Self { array_stride, attributes }
+  }
+
+  pub fn desc(&self) -> wgpu::VertexBufferLayout<'_> {
+    wgpu::VertexBufferLayout {
+      array_stride: self.array_stride,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &self.attributes,
+    }
+  }
+}
+
+// Checks that `buffers` together feed every shader input with a matching format.
+pub fn check_layout(inputs: &[VertexInput], buffers: &[wgpu::VertexBufferLayout]) -> Result<(), Vec<LayoutMismatch>> {
+  let attributes: Vec<&wgpu::VertexAttribute> = buffers.iter().flat_map(|buffer| buffer.attributes).collect();
+  let mut mismatches = Vec::new();
+
+  for input in inputs {
+    match attributes.iter().find(|attribute| attribute.shader_location == input.location) {
+      None => mismatches.push(LayoutMismatch::MissingLocation {
+        location: input.location,
+        name: input.name.clone(),
+        format: input.format,
+      }),
+      Some(attribute) if shader_visible_format(attribute.format) != input.format => mismatches.push(LayoutMismatch::Format {
+        location: input.location,
+        name: input.name.clone(),
+        shader: input.format,
+        buffer: attribute.format,
+      }),
+      Some(_) => {}
+    }
+  }
+
+  for attribute in attributes {
+    if !inputs.iter().any(|input| input.location == attribute.shader_location) {
+      mismatches.push(LayoutMismatch::UnusedLocation {
+        location: attribute.shader_location,
+        format: attribute.format,
+      });
+    }
+  }
+
+  if mismatches.is_empty() { Ok(()) } else { Err(mismatches) }
+}
+
+// Checks the inputs of `entry_point` against the layout of `V`.
+pub fn check_vertex_layout<V: VertexLayout>(module: &naga::Module, entry_point: &str) -> Result<(), ReflectError> {
+  let inputs = vertex_inputs(module, entry_point)?;
+  check_layout(&inputs, &[V::desc()]).map_err(ReflectError::Mismatch)
+}
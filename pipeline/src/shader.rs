@@ -0,0 +1,271 @@
+use std::borrow::Cow;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use crate::{config::ShaderLang, reflect, reflect::ReflectError, Instance, TexturedVertex, Vertex, VertexLayout};
+
+// Why naga rejected a shader, before wgpu gets to see it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderError {
+  // the source doesn't parse, or its vertex inputs can't be read or don't match the vertex buffers
+  Reflect { label: String, error: ReflectError },
+  // the diagnostics of naga's validator, with source spans
+  Validation { label: String, message: String },
+}
+
+impl fmt::Display for ShaderError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShaderError::Reflect { label, error: error @ ReflectError::Mismatch(_) } =>
+        write!(f, "{label} doesn't match its vertex buffers:\n{error}"),
+      ShaderError::Reflect { label, error } => write!(f, "{label}: {error}"),
+      ShaderError::Validation { message, .. } => write!(f, "{message}"),
+    }
+  }
+}
+
+impl std::error::Error for ShaderError {}
+
+fn reflect_error(label: &str) -> impl Fn(ReflectError) -> ShaderError + '_ {
+  move |error| ShaderError::Reflect { label: label.to_string(), error }
+}
+
+// The vertex and fragment stages of the triangle pipeline. WGSL keeps both
+// entry points in one module, GLSL needs a module per stage.
+pub(crate) struct ShaderModules {
+  vertex: wgpu::ShaderModule,
+  fragment: Option<wgpu::ShaderModule>,
+  pub vertex_entry: &'static str,
+  pub fragment_entry: &'static str,
+}
+
+impl ShaderModules {
+  pub fn vertex(&self) -> &wgpu::ShaderModule {
+    &self.vertex
+  }
+
+  pub fn fragment(&self) -> &wgpu::ShaderModule {
+    self.fragment.as_ref().unwrap_or(&self.vertex)
+  }
+}
+
+// The directory the shaders are baked in from, for loading them again at runtime.
+#[cfg(not(target_arch = "wasm32"))]
+fn src_dir() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src")
+}
+
+// Source code of the triangle shaders, either baked into the binary or read from `src/`.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ShaderSources {
+  lang: ShaderLang,
+  vertex: Cow<'static, str>,
+  fragment: Option<Cow<'static, str>>,
+}
+
+impl ShaderSources {
+  pub fn builtin(lang: ShaderLang) -> Self {
+    match lang {
+      ShaderLang::Wgsl => Self {
+        lang,
+        vertex: include_str!("triangle.wgsl").into(),
+        fragment: None,
+      },
+      ShaderLang::Glsl => Self {
+        lang,
+        vertex: include_str!("triangle.vert").into(),
+        fragment: Some(include_str!("triangle.frag").into()),
+      },
+    }
+  }
+
+  // The files `builtin` was compiled from, for loading them again at runtime.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn paths(lang: ShaderLang) -> Vec<PathBuf> {
+    let dir = src_dir();
+    match lang {
+      ShaderLang::Wgsl => vec![dir.join("triangle.wgsl")],
+      ShaderLang::Glsl => vec![dir.join("triangle.vert"), dir.join("triangle.frag")],
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn load(lang: ShaderLang) -> std::io::Result<Self> {
+    let mut sources = Self::paths(lang).into_iter().map(std::fs::read_to_string);
+    let vertex = sources.next().unwrap()?.into();
+    let fragment = sources.next().transpose()?.map(Cow::from);
+    Ok(Self { lang, vertex, fragment })
+  }
+
+  fn labels(&self) -> (&'static str, &'static str) {
+    match self.lang {
+      ShaderLang::Wgsl => ("triangle.wgsl", "triangle.wgsl"),
+      ShaderLang::Glsl => ("triangle.vert", "triangle.frag"),
+    }
+  }
+
+  // Runs the same checks wgpu would, plus the vertex layout check.
+  pub fn validate(&self) -> Result<(), ShaderError> {
+    let (vertex_label, fragment_label) = self.labels();
+
+    let (vertex, entry) = match self.lang {
+      ShaderLang::Wgsl => (reflect::parse_wgsl(&self.vertex), "vs_main"),
+      ShaderLang::Glsl => (reflect::parse_glsl(&self.vertex, naga::ShaderStage::Vertex), "main"),
+    };
+    let vertex = vertex.map_err(reflect_error(vertex_label))?;
+    validate_module(&vertex, &self.vertex, vertex_label)?;
+    reflect::check_vertex_layout::<Vertex>(&vertex, entry).map_err(reflect_error(vertex_label))?;
+
+    if let Some(fragment_source) = &self.fragment {
+      let fragment = reflect::parse_glsl(fragment_source, naga::ShaderStage::Fragment)
+        .map_err(reflect_error(fragment_label))?;
+      validate_module(&fragment, fragment_source, fragment_label)?;
+    }
+
+    Ok(())
+  }
+
+  pub fn create_modules(&self, device: &wgpu::Device) -> ShaderModules {
+    let (vertex_label, fragment_label) = self.labels();
+
+    match self.lang {
+      ShaderLang::Wgsl => ShaderModules {
+        vertex: device.create_shader_module(wgpu::ShaderModuleDescriptor {
+          label: Some(vertex_label),
+          source: wgpu::ShaderSource::Wgsl(self.vertex.clone()),
+        }),
+        fragment: None,
+        vertex_entry: "vs_main",
+        fragment_entry: "fs_main",
+      },
+      ShaderLang::Glsl => ShaderModules {
+        vertex: device.create_shader_module(wgpu::ShaderModuleDescriptor {
+          label: Some(vertex_label),
+          source: wgpu::ShaderSource::Glsl {
+            shader: self.vertex.clone(),
+            stage: naga::ShaderStage::Vertex,
+            defines: Default::default(),
+          },
+        }),
+        fragment: self.fragment.as_ref().map(|fragment| device.create_shader_module(wgpu::ShaderModuleDescriptor {
+          label: Some(fragment_label),
+          source: wgpu::ShaderSource::Glsl {
+            shader: fragment.clone(),
+            stage: naga::ShaderStage::Fragment,
+            defines: Default::default(),
+          },
+        })),
+        vertex_entry: "main",
+        fragment_entry: "main",
+      },
+    }
+  }
+}
+
+// Source code of a WGSL shader with `vs_main` and `fs_main` for one of the other draws.
+#[derive(Clone, PartialEq)]
+pub(crate) struct WgslSource {
+  label: &'static str,
+  source: Cow<'static, str>,
+}
+
+impl WgslSource {
+  pub fn textured() -> Self {
+    Self { label: "textured.wgsl", source: include_str!("textured.wgsl").into() }
+  }
+
+  pub fn instanced() -> Self {
+    Self { label: "instanced.wgsl", source: include_str!("instanced.wgsl").into() }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn path(&self) -> PathBuf {
+    src_dir().join(self.label)
+  }
+
+  // The same shader as it is on disk now.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn load(&self) -> std::io::Result<Self> {
+    Ok(Self { label: self.label, source: std::fs::read_to_string(self.path())?.into() })
+  }
+}
+
+// The shaders of every pipeline the scene draws with.
+#[derive(Clone, PartialEq)]
+pub(crate) struct SceneSources {
+  pub triangle: ShaderSources,
+  pub textured: WgslSource,
+  pub instanced: WgslSource,
+}
+
+impl SceneSources {
+  pub fn builtin(lang: ShaderLang) -> Self {
+    Self {
+      triangle: ShaderSources::builtin(lang),
+      textured: WgslSource::textured(),
+      instanced: WgslSource::instanced(),
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn paths(lang: ShaderLang) -> Vec<PathBuf> {
+    let mut paths = ShaderSources::paths(lang);
+    paths.push(WgslSource::textured().path());
+    paths.push(WgslSource::instanced().path());
+    paths
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn load(lang: ShaderLang) -> std::io::Result<Self> {
+    Ok(Self {
+      triangle: ShaderSources::load(lang)?,
+      textured: WgslSource::textured().load()?,
+      instanced: WgslSource::instanced().load()?,
+    })
+  }
+}
+
+pub(crate) fn validate_module(module: &naga::Module, source: &str, label: &str) -> Result<(), ShaderError> {
+  naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+    .validate(module)
+    .map(|_| ())
+    .map_err(|e| ShaderError::Validation { label: label.to_string(), message: e.emit_to_string_with_path(source, label) })
+}
+
+pub(crate) fn triangle_shaders(device: &wgpu::Device, sources: &ShaderSources) -> Result<ShaderModules, ShaderError> {
+  // fail with the offending @locations instead of a generic pipeline validation error
+  sources.validate()?;
+  Ok(sources.create_modules(device))
+}
+
+// Checked against `buffers` like the triangle shaders.
+fn wgsl_shaders(
+  device: &wgpu::Device,
+  source: &WgslSource,
+  buffers: &[wgpu::VertexBufferLayout],
+) -> Result<ShaderModules, ShaderError> {
+  let WgslSource { label, source } = source;
+  let module = reflect::parse_wgsl(source).map_err(reflect_error(label))?;
+  validate_module(&module, source, label)?;
+  let inputs = reflect::vertex_inputs(&module, "vs_main").map_err(reflect_error(label))?;
+  reflect::check_layout(&inputs, buffers).map_err(|e| reflect_error(label)(ReflectError::Mismatch(e)))?;
+
+  Ok(ShaderModules {
+    vertex: device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some(label),
+      source: wgpu::ShaderSource::Wgsl(source.clone()),
+    }),
+    fragment: None,
+    vertex_entry: "vs_main",
+    fragment_entry: "fs_main",
+  })
+}
+
+pub(crate) fn textured_shaders(device: &wgpu::Device, source: &WgslSource) -> Result<ShaderModules, ShaderError> {
+  wgsl_shaders(device, source, &[TexturedVertex::desc()])
+}
+
+pub(crate) fn instanced_shaders(device: &wgpu::Device, source: &WgslSource) -> Result<ShaderModules, ShaderError> {
+  wgsl_shaders(device, source, &[Vertex::desc(), Instance::desc()])
+}
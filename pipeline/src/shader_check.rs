@@ -0,0 +1,246 @@
+use std::{
+  fmt,
+  path::{Path, PathBuf},
+};
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+// A platform the shaders have to build for, as far as naga can tell without an adapter.
+struct Profile {
+  name: &'static str,
+  capabilities: Capabilities,
+  limits: wgpu::Limits,
+}
+
+fn profiles() -> [Profile; 3] {
+  [
+    Profile {
+      name: "WebGPU",
+      capabilities: Capabilities::MULTISAMPLED_SHADING | Capabilities::CUBE_ARRAY_TEXTURES,
+      limits: wgpu::Limits::default(),
+    },
+    Profile {
+      name: "WebGL2",
+      capabilities: Capabilities::empty(),
+      limits: wgpu::Limits::downlevel_webgl2_defaults(),
+    },
+    Profile {
+      name: "native",
+      capabilities: Capabilities::all(),
+      limits: wgpu::Limits::default(),
+    },
+  ]
+}
+
+#[derive(Debug)]
+struct LimitError(String);
+
+impl fmt::Display for LimitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl std::error::Error for LimitError {}
+
+enum ShaderKind {
+  Wgsl,
+  Glsl(naga::ShaderStage),
+}
+
+fn shader_kind(path: &Path) -> Option<ShaderKind> {
+  match path.extension()?.to_str()? {
+    "wgsl" => Some(ShaderKind::Wgsl),
+    "vert" => Some(ShaderKind::Glsl(naga::ShaderStage::Vertex)),
+    "frag" => Some(ShaderKind::Glsl(naga::ShaderStage::Fragment)),
+    "comp" => Some(ShaderKind::Glsl(naga::ShaderStage::Compute)),
+    _ => None,
+  }
+}
+
+// The `src/` directories of the renderer, pipeline, surface and wgpu-tutorial crates.
+pub fn default_dirs() -> Vec<PathBuf> {
+  let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+  ["renderer", "pipeline", "surface", "wgpu-tutorial"].iter()
+    .map(|name| root.join(name).join("src"))
+    .filter(|dir| dir.is_dir())
+    .collect()
+}
+
+fn find_shaders(dir: &Path, shaders: &mut Vec<PathBuf>) {
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    eprintln!("[check-shaders]: can't read {}", dir.display());
+    return;
+  };
+  for path in entries.flatten().map(|entry| entry.path()) {
+    if path.is_dir() {
+      find_shaders(&path, shaders);
+    } else if shader_kind(&path).is_some() {
+      shaders.push(path);
+    }
+  }
+}
+
+fn parse(kind: &ShaderKind, source: &str, label: &str) -> Result<naga::Module, Vec<String>> {
+  match kind {
+    ShaderKind::Wgsl => naga::front::wgsl::parse_str(source)
+      .map_err(|e| vec![e.emit_to_string_with_path(source, label)]),
+    ShaderKind::Glsl(stage) => naga::front::glsl::Frontend::default()
+      .parse(&naga::front::glsl::Options::from(*stage), source)
+      .map_err(|errors| errors.into_iter()
+        .map(|e| naga::WithSpan::new(e.kind).with_span(e.meta, "").emit_to_string_with_path(source, label))
+        .collect()),
+  }
+}
+
+// The limits naga doesn't know about. Resources are counted per module, so a
+// global shared by several entry points counts once against each stage.
+fn check_limits(module: &naga::Module, limits: &wgpu::Limits) -> Vec<naga::WithSpan<LimitError>> {
+  let mut errors = Vec::new();
+  let mut error = |message: String, span: naga::Span| {
+    errors.push(naga::WithSpan::new(LimitError(message)).with_span(span, "declared here"));
+  };
+
+  let (mut uniform_buffers, mut storage_buffers, mut storage_textures) = (0, 0, 0);
+  for (handle, global) in module.global_variables.iter() {
+    let span = module.global_variables.get_span(handle);
+
+    if let Some(binding) = &global.binding {
+      if binding.group >= limits.max_bind_groups {
+        error(format!("@group({}) exceeds max_bind_groups ({})", binding.group, limits.max_bind_groups), span);
+      }
+    }
+
+    match global.space {
+      naga::AddressSpace::Uniform => {
+        uniform_buffers += 1;
+        if uniform_buffers > limits.max_uniform_buffers_per_shader_stage {
+          error(format!("more than {} uniform buffers", limits.max_uniform_buffers_per_shader_stage), span);
+        }
+      }
+      naga::AddressSpace::Storage { .. } => {
+        storage_buffers += 1;
+        if storage_buffers > limits.max_storage_buffers_per_shader_stage {
+          error(format!("more than {} storage buffers", limits.max_storage_buffers_per_shader_stage), span);
+        }
+      }
+      naga::AddressSpace::Handle => {
+        if let naga::TypeInner::Image { class: naga::ImageClass::Storage { .. }, .. } = module.types[global.ty].inner {
+          storage_textures += 1;
+          if storage_textures > limits.max_storage_textures_per_shader_stage {
+            error(format!("more than {} storage textures", limits.max_storage_textures_per_shader_stage), span);
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  for entry in &module.entry_points {
+    match entry.stage {
+      naga::ShaderStage::Compute if limits.max_compute_workgroups_per_dimension == 0 => {
+        error(format!("compute entry point `{}` isn't supported", entry.name), naga::Span::default());
+      }
+      naga::ShaderStage::Compute => {
+        let [x, y, z] = entry.workgroup_size;
+        let max = [
+          limits.max_compute_workgroup_size_x,
+          limits.max_compute_workgroup_size_y,
+          limits.max_compute_workgroup_size_z,
+        ];
+        // a product that doesn't fit in a u32 is over any limit
+        let invocations = x.checked_mul(y).and_then(|xy| xy.checked_mul(z));
+        let too_many = invocations.is_none_or(|n| n > limits.max_compute_invocations_per_workgroup);
+        if x > max[0] || y > max[1] || z > max[2] || too_many {
+          error(format!(
+            "workgroup size {:?} of `{}` exceeds {:?} or {} invocations",
+            entry.workgroup_size, entry.name, max, limits.max_compute_invocations_per_workgroup
+          ), naga::Span::default());
+        }
+      }
+      naga::ShaderStage::Vertex => {
+        let inputs = crate::reflect::vertex_inputs(module, &entry.name).map_or(0, |inputs| inputs.len() as u32);
+        if inputs > limits.max_vertex_attributes {
+          error(format!("`{}` has more than {} inputs", entry.name, limits.max_vertex_attributes), naga::Span::default());
+        }
+      }
+      naga::ShaderStage::Fragment => {}
+    }
+  }
+
+  errors
+}
+
+// Profiles a shader opts out of with a `// check-shaders: skip WebGL2, ...` line, for shaders
+// that are only used where the platform supports them, like compute on WebGL2.
+fn skipped_profiles(source: &str) -> Vec<&str> {
+  source.lines()
+    .filter_map(|line| line.trim().strip_prefix("// check-shaders: skip "))
+    .flat_map(|names| names.split(','))
+    .map(str::trim)
+    .collect()
+}
+
+// Validates every shader under `dirs` against each profile and prints the
+// diagnostics. Returns false if any shader fails.
+pub fn check(dirs: &[PathBuf]) -> bool {
+  let mut shaders = Vec::new();
+  for dir in dirs {
+    find_shaders(dir, &mut shaders);
+  }
+  shaders.sort();
+
+  let mut failures = 0;
+  for path in &shaders {
+    let label = path.display().to_string();
+    let kind = shader_kind(path).unwrap();
+
+    let source = match std::fs::read_to_string(path) {
+      Ok(source) => source,
+      Err(e) => {
+        eprintln!("[check-shaders]: can't read {label}: {e}");
+        failures += 1;
+        continue;
+      }
+    };
+
+    let module = match parse(&kind, &source, &label) {
+      Ok(module) => module,
+      Err(errors) => {
+        eprintln!("[check-shaders]: {label} doesn't parse");
+        for e in errors {
+          eprintln!("{e}");
+        }
+        failures += 1;
+        continue;
+      }
+    };
+
+    let skipped = skipped_profiles(&source);
+    for profile in profiles() {
+      if skipped.contains(&profile.name) {
+        println!("[check-shaders]: {label} skipped for {}", profile.name);
+        continue;
+      }
+
+      let mut errors = Vec::new();
+      if let Err(e) = Validator::new(ValidationFlags::all(), profile.capabilities).validate(&module) {
+        errors.push(e.emit_to_string_with_path(&source, &label));
+      }
+      errors.extend(check_limits(&module, &profile.limits).into_iter().map(|e| e.emit_to_string_with_path(&source, &label)));
+
+      if errors.is_empty() {
+        println!("[check-shaders]: {label} ok for {}", profile.name);
+      } else {
+        eprintln!("[check-shaders]: {label} fails for {}", profile.name);
+        for e in errors {
+          eprintln!("{e}");
+        }
+        failures += 1;
+      }
+    }
+  }
+
+  println!("[check-shaders]: {} shaders checked, {} failures", shaders.len(), failures);
+  failures == 0
+}
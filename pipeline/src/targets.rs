@@ -0,0 +1,142 @@
+use crate::depth::DepthTexture;
+
+pub(crate) const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+// What the device validates textures of `format` against: the adapter's own features with
+// `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`, otherwise only those WebGPU guarantees.
+fn sample_count_supported(adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat, count: u32) -> bool {
+  let mut flags = adapter.get_texture_format_features(format).flags;
+  if !device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+    flags &= format.guaranteed_format_features(device.features()).flags;
+  }
+  flags.sample_count_supported(count)
+}
+
+// The largest sample count up to `requested` that every format supports on this device, never
+// more than was asked for. 1 always works, WebGPU and WebGL2 only guarantee 4 on top of that.
+pub fn supported_sample_count(
+  adapter: &wgpu::Adapter,
+  device: &wgpu::Device,
+  formats: &[wgpu::TextureFormat],
+  requested: u32,
+) -> u32 {
+  let supported = SAMPLE_COUNTS.into_iter().rev()
+    .filter(|&count| 1 < count && count <= requested)
+    .find(|&count| formats.iter().all(|&format| sample_count_supported(adapter, device, format, count)))
+    .unwrap_or(1);
+
+  if supported != requested {
+    log::warn!("[msaa]: {}x isn't supported for {:?}, using {}x", requested, formats, supported);
+  }
+  supported
+}
+
+// Multisampled color texture that resolves into the final view.
+struct MultisampledTexture {
+  _texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+impl MultisampledTexture {
+  fn new(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Multisampled Color Texture"),
+      size: wgpu::Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    Self { _texture: texture, view }
+  }
+}
+
+// Everything a frame is drawn into besides the final color view: the multisampled color
+// texture when MSAA is on and the depth/stencil texture when depth is on. Both have to be
+// the size of the final view, so `resize` them along with it.
+pub(crate) struct RenderTargets {
+  color_format: wgpu::TextureFormat,
+  depth_format: Option<wgpu::TextureFormat>,
+  sample_count: u32,
+  width: u32,
+  height: u32,
+  multisampled: Option<MultisampledTexture>,
+  depth: Option<DepthTexture>,
+}
+
+impl RenderTargets {
+  pub fn new(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    color_format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    sample_count: u32,
+  ) -> Self {
+    let mut targets = Self {
+      color_format,
+      depth_format,
+      sample_count,
+      width: 0,
+      height: 0,
+      multisampled: None,
+      depth: None,
+    };
+    targets.resize(device, width, height);
+    targets
+  }
+
+  pub fn color_format(&self) -> wgpu::TextureFormat {
+    self.color_format
+  }
+
+  pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+    self.depth_format
+  }
+
+  pub fn sample_count(&self) -> u32 {
+    self.sample_count
+  }
+
+  // Recreates the textures if the size changed.
+  pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    if (self.width, self.height) == (width, height) {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+
+    self.multisampled = (self.sample_count > 1).then(|| {
+      MultisampledTexture::new(device, width, height, self.color_format, self.sample_count)
+    });
+    self.depth = self.depth_format.map(|format| DepthTexture::new(device, width, height, format, self.sample_count));
+  }
+
+  // Draws into the multisampled texture and resolves into `view`, or into `view` directly.
+  pub fn color_attachment<'a>(&'a self, view: &'a wgpu::TextureView, ops: wgpu::Operations<wgpu::Color>) -> wgpu::RenderPassColorAttachment<'a> {
+    match &self.multisampled {
+      Some(multisampled) => wgpu::RenderPassColorAttachment {
+        view: &multisampled.view,
+        resolve_target: Some(view),
+        ops,
+      },
+      None => wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops,
+      },
+    }
+  }
+
+  pub fn depth_attachment(&self) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+    self.depth.as_ref().map(DepthTexture::attachment)
+  }
+}
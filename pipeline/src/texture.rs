@@ -0,0 +1,269 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TextureError {
+  Decode(image::ImageError),
+  // the pixel data doesn't cover width * height
+  Size { width: u32, height: u32, len: usize },
+  // a side is longer than the device's `max_texture_dimension_2d`
+  TooLarge { width: u32, height: u32, max: u32 },
+  #[cfg(not(target_arch = "wasm32"))]
+  Io(std::io::Error),
+}
+
+impl fmt::Display for TextureError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TextureError::Decode(e) => write!(f, "failed to decode image: {e}"),
+      TextureError::Size { width, height, len } => write!(f, "{len} bytes is not a {width}x{height} RGBA8 image"),
+      TextureError::TooLarge { width, height, max } => {
+        write!(f, "{width}x{height} is larger than the {max}x{max} textures the device supports")
+      }
+      #[cfg(not(target_arch = "wasm32"))]
+      TextureError::Io(e) => write!(f, "failed to read image: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for TextureError {}
+
+impl From<image::ImageError> for TextureError {
+  fn from(e: image::ImageError) -> Self {
+    TextureError::Decode(e)
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<std::io::Error> for TextureError {
+  fn from(e: std::io::Error) -> Self {
+    TextureError::Io(e)
+  }
+}
+
+// A sampled sRGB color texture with a full mip chain and a trilinear, repeating sampler.
+pub struct Texture {
+  pub texture: wgpu::Texture,
+  pub view: wgpu::TextureView,
+  pub sampler: wgpu::Sampler,
+  // the top level as uploaded, for `recreate`
+  pixels: Vec<u8>,
+  label: String,
+}
+
+impl Texture {
+  pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn from_path(device: &wgpu::Device, queue: &wgpu::Queue, path: impl AsRef<std::path::Path>) -> Result<Self, TextureError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    Self::from_bytes(device, queue, &bytes, &path.display().to_string())
+  }
+
+  // PNG or JPEG, told apart by the file header.
+  pub fn from_bytes(device: &wgpu::Device, queue: &wgpu::Queue, bytes: &[u8], label: &str) -> Result<Self, TextureError> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    let (width, height) = image.dimensions();
+    Self::from_rgba8(device, queue, width, height, &image, label)
+  }
+
+  // Uploads tightly packed RGBA8 rows, top row first, and fills in the smaller mip levels.
+  pub fn from_rgba8(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    label: &str,
+  ) -> Result<Self, TextureError> {
+    if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+      return Err(TextureError::Size { width, height, len: pixels.len() });
+    }
+    let max = device.limits().max_texture_dimension_2d;
+    if width > max || height > max {
+      return Err(TextureError::TooLarge { width, height, max });
+    }
+
+    let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(label),
+      size,
+      mip_level_count: size.max_mips(wgpu::TextureDimension::D2),
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: Self::FORMAT,
+      // mip levels are rendered from the level above
+      usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[],
+    });
+
+    queue.write_texture(
+      wgpu::ImageCopyTexture {
+        texture: &texture,
+        mip_level: 0,
+        origin: wgpu::Origin3d::ZERO,
+        aspect: wgpu::TextureAspect::All,
+      },
+      pixels,
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(width * 4),
+        rows_per_image: Some(height),
+      },
+      size,
+    );
+
+    generate_mipmaps(device, queue, &texture);
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some(label),
+      address_mode_u: wgpu::AddressMode::Repeat,
+      address_mode_v: wgpu::AddressMode::Repeat,
+      address_mode_w: wgpu::AddressMode::Repeat,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    Ok(Self { texture, view, sampler, pixels: pixels.to_vec(), label: label.to_string() })
+  }
+
+  // The same texture on another device, e.g. after the one it was created on was lost.
+  pub fn recreate(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self, TextureError> {
+    Self::from_rgba8(device, queue, self.texture.width(), self.texture.height(), &self.pixels, &self.label)
+  }
+
+  pub fn mip_level_count(&self) -> u32 {
+    self.texture.mip_level_count()
+  }
+
+  // @binding(0) is the texture, @binding(1) the sampler.
+  pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some("Texture Bind Group Layout"),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+          count: None,
+        },
+      ],
+    })
+  }
+
+  pub fn bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Texture Bind Group"),
+      layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&self.view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(&self.sampler),
+        },
+      ],
+    })
+  }
+}
+
+// Renders every mip level from the one above with a linear-filtered fullscreen blit.
+fn generate_mipmaps(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+  if texture.mip_level_count() < 2 {
+    return;
+  }
+
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("mipmap.wgsl"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("mipmap.wgsl").into()),
+  });
+
+  let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some("Mipmap Pipeline"),
+    layout: None,
+    vertex: wgpu::VertexState {
+      module: &shader,
+      entry_point: "vs_main",
+      buffers: &[],
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: &shader,
+      entry_point: "fs_main",
+      targets: &[Some(texture.format().into())],
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+  });
+
+  let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+    label: Some("Mipmap Sampler"),
+    mag_filter: wgpu::FilterMode::Linear,
+    min_filter: wgpu::FilterMode::Linear,
+    ..Default::default()
+  });
+
+  let views: Vec<_> = (0..texture.mip_level_count()).map(|level| {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+      label: Some("Mip Level View"),
+      base_mip_level: level,
+      mip_level_count: Some(1),
+      ..Default::default()
+    })
+  }).collect();
+
+  let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+    label: Some("Mipmap Encoder"),
+  });
+
+  for pair in views.windows(2) {
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Mipmap Bind Group"),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&pair[0]),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(&sampler),
+        },
+      ],
+    });
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Mipmap Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: &pair[1],
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      ..Default::default()
+    });
+    render_pass.set_pipeline(&pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+
+  queue.submit(std::iter::once(encoder.finish()));
+}
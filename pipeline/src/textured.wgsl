@@ -0,0 +1,37 @@
+// 顶点着色器
+struct CameraUniform {
+    view_proj: mat4x4f,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3f,
+    @location(1) uv: vec2f,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) uv: vec2f,
+};
+
+@vertex
+fn vs_main(
+  in: VertexInput
+) -> VertexOutput {
+  var out: VertexOutput;
+  out.uv = in.uv;
+  out.clip_position = camera.view_proj * vec4f(in.position, 1.0);
+  return out;
+}
+
+// 片元着色器
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    return textureSample(t_diffuse, s_diffuse, in.uv);
+}
@@ -0,0 +1,18 @@
+// 色调映射：曝光后用 ACES 曲线把颜色压到 [0, 1]
+@group(0) @binding(0)
+var input_sampler: sampler;
+@group(0) @binding(1)
+var input: texture_2d<f32>;
+// x: 曝光
+@group(0) @binding(2)
+var<uniform> params: vec4f;
+
+fn aces(x: vec3f) -> vec3f {
+  return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3f(0.0), vec3f(1.0));
+}
+
+@fragment
+fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4f {
+  let color = textureSample(input, input_sampler, uv);
+  return vec4f(aces(color.rgb * params.x), color.a);
+}
@@ -0,0 +1,16 @@
+#version 450
+
+// 顶点着色器
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+
+layout(location = 0) out vec3 v_color;
+
+layout(set = 0, binding = 0) uniform CameraUniform {
+  mat4 view_proj;
+};
+
+void main() {
+  v_color = color;
+  gl_Position = view_proj * vec4(position, 1.0);
+}
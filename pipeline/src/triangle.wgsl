@@ -1,4 +1,10 @@
 // 顶点着色器
+struct CameraUniform {
+    view_proj: mat4x4f,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
 struct VertexInput {
     @location(0) position: vec3f,
     @location(1) color: vec3f,
@@ -15,7 +21,7 @@ fn vs_main(
 ) -> VertexOutput {
   var out: VertexOutput;
   out.color = in.color;
-  out.clip_position = vec4f(in.position, 1.0);
+  out.clip_position = camera.view_proj * vec4f(in.position, 1.0);
   return out;
 }
 
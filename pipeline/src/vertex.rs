@@ -0,0 +1,63 @@
+// A vertex type that can be uploaded to a vertex buffer as is.
+//
+// `bytemuck::Pod` rejects `#[repr(C)]` structs with padding at compile time, so the byte
+// view is always valid. Implement it with `vertex_layout!` rather than by hand, which takes
+// the attribute offsets from the struct and checks each field against its vertex format.
+pub trait VertexLayout: bytemuck::Pod {
+  const ATTRIBUTES: &'static [wgpu::VertexAttribute];
+  const STEP_MODE: wgpu::VertexStepMode;
+
+  fn desc() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+      step_mode: Self::STEP_MODE,
+      attributes: Self::ATTRIBUTES,
+    }
+  }
+
+  fn as_bytes(vertices: &[Self]) -> &[u8] {
+    bytemuck::cast_slice(vertices)
+  }
+}
+
+// Lets `vertex_layout!` name a field's type in a const context.
+#[doc(hidden)]
+pub const fn field_size<T, F>(_field: fn(&T) -> &F) -> usize {
+  std::mem::size_of::<F>()
+}
+
+// vertex_layout!(Vertex {
+//   position => 0: Float32x3,
+//   color => 1: Float32x3,
+// });
+//
+// Per-instance data takes the step mode first: `vertex_layout!(Instance, Instance { .. })`.
+#[macro_export]
+macro_rules! vertex_layout {
+  ($vertex:ty { $($field:ident => $location:literal : $format:ident),* $(,)? }) => {
+    $crate::vertex_layout!($vertex, Vertex { $($field => $location: $format),* });
+  };
+  ($vertex:ty, $step_mode:ident { $($field:ident => $location:literal : $format:ident),* $(,)? }) => {
+    impl $crate::VertexLayout for $vertex {
+      const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &[
+        $(
+          wgpu::VertexAttribute {
+            offset: std::mem::offset_of!($vertex, $field) as wgpu::BufferAddress,
+            shader_location: $location,
+            format: wgpu::VertexFormat::$format,
+          },
+        )*
+      ];
+      const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::$step_mode;
+    }
+
+    const _: () = {
+      $(
+        assert!(
+          $crate::vertex::field_size(|v: &$vertex| &v.$field) as u64 == wgpu::VertexFormat::$format.size(),
+          concat!("`", stringify!($vertex), "::", stringify!($field), "` doesn't match ", stringify!($format)),
+        );
+      )*
+    };
+  };
+}
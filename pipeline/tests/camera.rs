@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use glam::{Vec3, Vec4};
+use pipeline::{Camera, FlyController, OrbitController, Projection};
+
+fn project(camera: &Camera, point: Vec3) -> Vec3 {
+  let clip = camera.view_proj() * Vec4::new(point.x, point.y, point.z, 1.0);
+  clip.truncate() / clip.w
+}
+
+fn assert_near(a: Vec3, b: Vec3) {
+  assert!(a.abs_diff_eq(b, 1e-4), "{a} != {b}");
+}
+
+#[test]
+fn default_cameras_frame_the_clip_space_square() {
+  for projection in [Projection::perspective(), Projection::orthographic()] {
+    let camera = Camera::new(projection);
+    for corner in [Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.58, -0.5, 0.0)] {
+      let ndc = project(&camera, corner);
+      assert_near(Vec3::new(ndc.x, ndc.y, 0.0), corner);
+      assert!((0.0..=1.0).contains(&ndc.z));
+    }
+  }
+}
+
+#[test]
+fn aspect_ratio_widens_the_view() {
+  let camera = Camera { aspect: 2.0, ..Camera::default() };
+  let ndc = project(&camera, Vec3::new(2.0, 1.0, 0.0));
+  assert_near(Vec3::new(ndc.x, ndc.y, 0.0), Vec3::new(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn orbit_keeps_its_distance_to_the_target() {
+  let mut camera = Camera { target: Vec3::new(0.0, 0.0, 0.5), ..Camera::default() };
+  let mut orbit = OrbitController::new(&camera);
+  let distance = orbit.distance;
+
+  orbit.rotate(300.0, -100.0);
+  orbit.update_camera(&mut camera);
+  assert!((camera.eye.distance(camera.target) - distance).abs() < 1e-4);
+  assert_ne!(camera.eye.x, 0.0);
+
+  orbit.zoom(1.0);
+  orbit.update_camera(&mut camera);
+  assert!(camera.eye.distance(camera.target) < distance);
+}
+
+#[test]
+fn orbit_starts_where_the_camera_is() {
+  let mut camera = Camera::default();
+  let eye = camera.eye;
+  OrbitController::new(&camera).update_camera(&mut camera);
+  assert_near(camera.eye, eye);
+}
+
+#[test]
+fn fly_looks_where_the_camera_looks() {
+  let mut camera = Camera::default();
+  let before = camera;
+  FlyController::new(&camera).update_camera(&mut camera, Duration::from_millis(16));
+  // nothing held, so it stays put
+  assert_near(camera.eye, before.eye);
+  assert_near((camera.target - camera.eye).normalize(), (before.target - before.eye).normalize());
+}
+
+#[test]
+fn orbit_zoom_narrows_an_orthographic_view() {
+  let mut camera = Camera::new(Projection::orthographic());
+  let mut orbit = OrbitController::new(&camera);
+  orbit.update_camera(&mut camera);
+  assert_eq!(camera.projection, Projection::orthographic());
+
+  // zooming in makes the same point land further from the center
+  let before = project(&camera, Vec3::new(0.5, 0.5, 0.0));
+  orbit.zoom(2.0);
+  orbit.update_camera(&mut camera);
+  let after = project(&camera, Vec3::new(0.5, 0.5, 0.0));
+  assert!(after.x > before.x * 1.2 && after.y > before.y * 1.2, "{before} -> {after}");
+}
@@ -0,0 +1,39 @@
+use pipeline::{CameraMode, Config, DepthFormat, Effect, ShaderLang};
+
+fn parse(s: &str) -> Result<Config, String> {
+  Config::parse(s.split_whitespace().map(String::from))
+}
+
+#[test]
+fn flags_take_their_value_either_way() {
+  let config = parse("--shader glsl --camera=fly --depth depth24plusstencil8 --msaa=4 --post grayscale,blur --ortho").unwrap();
+  assert_eq!(config.shader, ShaderLang::Glsl);
+  assert_eq!(config.camera, CameraMode::Fly);
+  assert_eq!(config.depth, Some(DepthFormat::Depth24PlusStencil8));
+  assert_eq!(config.sample_count, 4);
+  assert_eq!(config.post, [Effect::Grayscale, Effect::Blur]);
+  assert!(config.ortho);
+
+  assert_eq!(parse("--depth=none").unwrap().depth, None);
+  assert_eq!(parse("--max-fps 30").unwrap().max_fps, Some(30.0));
+}
+
+#[test]
+fn bad_arguments_are_rejected() {
+  for args in [
+    "--shader hlsl",
+    "--particles many",
+    "--instances -1",
+    "--msaa 3",
+    "--post grayscale,sharpen",
+    "--render-scale 0",
+    "--frame-latency 0",
+    "--max-fps inf",
+    "--max-fps 1e-30",
+    "--present-mode vsync",
+    "--texture",
+    "--verbose",
+  ] {
+    assert!(parse(args).is_err(), "{args}");
+  }
+}
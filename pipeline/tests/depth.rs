@@ -0,0 +1,33 @@
+use pipeline::{Config, HeadlessState, Indices, MeshData, Vertex};
+
+// A red quad in front of a blue one, with the blue one drawn last.
+fn overlapping_quads() -> MeshData<Vertex> {
+  let quad = |z: f32, color: [f32; 3]| [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]]
+    .map(|[x, y]| Vertex { position: [x, y, z], color });
+
+  let mut vertices = quad(0.2, [1.0, 0.0, 0.0]).to_vec();
+  vertices.extend(quad(-0.2, [0.0, 0.0, 1.0]));
+  MeshData { vertices, indices: Some(Indices::U16(vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7])) }
+}
+
+async fn center_pixel(args: &[&str]) -> [u8; 4] {
+  let config = Config::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+  let mut state = HeadlessState::with_config(32, 32, &config).await;
+  state.set_mesh(&overlapping_quads());
+
+  let pixels = state.render();
+  let i = (16 * 32 + 16) * 4;
+  pixels[i..i + 4].try_into().unwrap()
+}
+
+#[tokio::test]
+async fn nearer_geometry_wins_with_a_depth_buffer() {
+  for format in ["depth32float", "depth24plusstencil8"] {
+    assert_eq!(center_pixel(&["--depth", format]).await, [255, 0, 0, 255], "{format}");
+  }
+}
+
+#[tokio::test]
+async fn last_drawn_geometry_wins_without_one() {
+  assert_eq!(center_pixel(&["--depth=none"]).await, [0, 0, 255, 255]);
+}
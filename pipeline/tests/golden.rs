@@ -0,0 +1,45 @@
+use renderer::testing::{Golden, Tolerance};
+
+fn golden() -> Golden {
+  Golden::new(env!("CARGO_MANIFEST_DIR"), env!("CARGO_TARGET_TMPDIR"))
+}
+
+#[tokio::test]
+async fn triangle_matches_reference() {
+  let state = pipeline::HeadlessState::new(128, 128).await;
+  let (width, height) = state.size();
+  let pixels = state.render();
+
+  golden().assert_matches("triangle", width, height, &pixels, Tolerance {
+    per_channel: 2,
+    max_failing_pixels: 64,
+  });
+}
+
+#[tokio::test]
+async fn glsl_triangle_matches_reference() {
+  let config = pipeline::Config::parse(["--shader=glsl".to_string()]).unwrap();
+  let state = pipeline::HeadlessState::with_config(128, 128, &config).await;
+  let (width, height) = state.size();
+  let pixels = state.render();
+
+  // the GLSL port has to draw exactly what triangle.wgsl draws
+  golden().assert_matches("triangle", width, height, &pixels, Tolerance {
+    per_channel: 2,
+    max_failing_pixels: 64,
+  });
+}
+
+#[tokio::test]
+async fn hot_reload_starts_with_the_shaders_on_disk() {
+  // the files the built in shaders come from, so the triangle looks the same
+  let config = pipeline::Config::parse(["--hot-reload".to_string()]).unwrap();
+  let state = pipeline::HeadlessState::with_config(128, 128, &config).await;
+  let (width, height) = state.size();
+  let pixels = state.render();
+
+  golden().assert_matches("triangle", width, height, &pixels, Tolerance {
+    per_channel: 2,
+    max_failing_pixels: 64,
+  });
+}
@@ -0,0 +1,101 @@
+use pipeline::{instance_grid, Config, HeadlessState, Instance, Instances};
+
+#[test]
+fn ids_survive_removing_other_instances() {
+  let mut instances = Instances::new();
+  let a = instances.add(Instance::new([0.0, 0.0, 0.0], 1.0, [1.0; 4]));
+  let b = instances.add(Instance::new([1.0, 0.0, 0.0], 1.0, [1.0; 4]));
+  let c = instances.add(Instance::new([2.0, 0.0, 0.0], 1.0, [1.0; 4]));
+
+  assert_eq!(instances.remove(a).unwrap().position, [0.0, 0.0, 0.0]);
+  assert_eq!(instances.remove(a), None);
+  assert_eq!(instances.len(), 2);
+  assert_eq!(instances.get(b).unwrap().position, [1.0, 0.0, 0.0]);
+  assert_eq!(instances.get(c).unwrap().position, [2.0, 0.0, 0.0]);
+
+  assert!(instances.update(c, Instance::new([3.0, 0.0, 0.0], 1.0, [1.0; 4])));
+  assert!(!instances.update(a, Instance::default()));
+  assert_eq!(instances.get(c).unwrap().position, [3.0, 0.0, 0.0]);
+}
+
+#[test]
+fn dirty_ranges_are_merged() {
+  let mut instances = instance_grid(10);
+  assert_eq!(instances.dirty_ranges(), vec![0..10]);
+
+  let ids: Vec<_> = (0..3).map(|_| instances.add(Instance::default())).collect();
+  instances.update(ids[0], Instance::new([1.0; 3], 1.0, [1.0; 4]));
+  // the last instance moves into the gap at index 11
+  instances.remove(ids[1]);
+  assert_eq!(instances.dirty_ranges(), vec![0..12]);
+}
+
+#[tokio::test]
+async fn only_changed_instances_are_uploaded() {
+  let config = Config::parse(["--instances=100".to_string()]).unwrap();
+  let mut state = HeadlessState::with_config(32, 32, &config).await;
+  let stride = std::mem::size_of::<Instance>() as u64;
+
+  assert_eq!(state.update_instances(|_| {}), 0);
+
+  let mut id = None;
+  let written = state.update_instances(|instances| {
+    id = Some(instances.add(Instance::default()));
+  });
+  assert_eq!(written, stride);
+
+  // two updates next to each other go out as one write
+  let written = state.update_instances(|instances| {
+    instances.update(id.unwrap(), Instance::new([0.5; 3], 1.0, [1.0; 4]));
+    instances.remove(id.unwrap());
+  });
+  assert_eq!(written, 0);
+}
+
+#[tokio::test]
+async fn instances_are_drawn_in_one_call() {
+  let mut state = HeadlessState::with_config(64, 64, &Config::default()).await;
+  let mut right = None;
+  state.update_instances(|instances| {
+    instances.add(Instance::new([-0.5, 0.0, 0.0], 0.5, [1.0, 0.0, 0.0, 1.0]));
+    right = Some(instances.add(Instance::new([0.5, 0.0, 0.0], 0.5, [0.0, 0.0, 1.0, 1.0])));
+  });
+
+  let pixel = |pixels: &[u8], x: usize, y: usize| -> [u8; 4] {
+    let i = (y * 64 + x) * 4;
+    pixels[i..i + 4].try_into().unwrap()
+  };
+
+  let pixels = state.render();
+  let [r, g, b, _] = pixel(&pixels, 16, 32);
+  assert!(r > 0 && g == 0 && b == 0, "{:?}", [r, g, b]);
+  let [r, g, b, _] = pixel(&pixels, 48, 32);
+  assert!(r == 0 && g == 0 && b > 0, "{:?}", [r, g, b]);
+
+  state.update_instances(|instances| {
+    instances.remove(right.unwrap());
+  });
+  // back to the clear color
+  assert_eq!(pixel(&state.render(), 48, 32), [89, 124, 149, 255]);
+}
+
+#[tokio::test]
+async fn instances_survive_a_lost_device() {
+  let mut state = HeadlessState::with_config(64, 64, &Config::parse(["--post=tonemap".to_string()]).unwrap()).await;
+  state.update_instances(|instances| {
+    instances.add(Instance::new([-0.5, 0.0, 0.0], 0.5, [1.0, 0.0, 0.0, 1.0]));
+  });
+  assert!(state.post().unwrap().set_params(state.queue(), "tonemap0", [0.5, 0.0, 0.0, 0.0]));
+  let before = state.render();
+  assert!(!state.recover_device().unwrap());
+
+  state.device().destroy();
+  state.device().poll(wgpu::Maintain::Wait);
+  assert!(state.is_device_lost());
+
+  assert!(state.recover_device().unwrap());
+  assert!(!state.is_device_lost());
+  // nothing left to upload, the instance went to the new device with the rest of the scene
+  assert_eq!(state.update_instances(|_| {}), 0);
+  assert_eq!(state.render(), before);
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use pipeline::{Indices, LoadError, Model, Vertex};
+
+fn write_fixture(name: &str, contents: &str) -> PathBuf {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("loader");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(name);
+  std::fs::write(&path, contents).unwrap();
+  path
+}
+
+#[test]
+fn obj_quad_is_triangulated_with_material_colors() {
+  write_fixture("quad.mtl", "newmtl red\nKd 1.0 0.0 0.0\n");
+  let path = write_fixture("quad.obj", "\
+mtllib quad.mtl
+o quad
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+");
+
+  let model = Model::load(&path).unwrap();
+  assert_eq!(model.meshes.len(), 1);
+
+  let mesh = &model.meshes[0];
+  assert_eq!(mesh.name, "quad");
+  assert_eq!(mesh.positions.len(), 4);
+  assert_eq!(mesh.indices.len(), 6);
+  assert!(mesh.colors.iter().all(|&color| color == [1.0, 0.0, 0.0]));
+  assert_eq!(mesh.normals.as_ref().unwrap()[0], [0.0, 0.0, 1.0]);
+  // flipped to wgpu's top-left origin
+  assert_eq!(mesh.uvs.as_ref().unwrap()[0], [0.0, 1.0]);
+
+  let data = model.to_mesh_data();
+  assert_eq!(data.vertices.len(), 4);
+  assert_eq!(data.vertices[0], Vertex { position: [-1.0, -1.0, 0.0], color: [1.0, 0.0, 0.0] });
+  assert!(matches!(data.indices, Some(Indices::U16(ref indices)) if indices.len() == 6));
+}
+
+// one triangle, u16 indices, placed by a node translated along x
+const TRIANGLE_GLTF: &str = r#"{
+  "asset": { "version": "2.0" },
+  "scene": 0,
+  "scenes": [{ "nodes": [0] }],
+  "nodes": [{ "mesh": 0, "translation": [1.0, 0.0, 0.0] }],
+  "meshes": [{
+    "name": "triangle",
+    "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+  }],
+  "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [0.0, 1.0, 0.0, 1.0] } }],
+  "buffers": [{
+    "byteLength": 44,
+    "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="
+  }],
+  "bufferViews": [
+    { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+    { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+  ],
+  "accessors": [
+    { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0] },
+    { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+  ]
+}"#;
+
+#[test]
+fn gltf_applies_node_transforms_and_base_color() {
+  let path = write_fixture("triangle.gltf", TRIANGLE_GLTF);
+
+  let model = Model::load(&path).unwrap();
+  assert_eq!(model.meshes.len(), 1);
+
+  let mesh = &model.meshes[0];
+  assert_eq!(mesh.name, "triangle");
+  assert_eq!(mesh.positions, vec![[1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [1.0, 1.0, 0.0]]);
+  assert_eq!(mesh.colors, vec![[0.0, 1.0, 0.0]; 3]);
+  assert_eq!(mesh.indices, vec![0, 1, 2]);
+  assert!(mesh.normals.is_none());
+  assert!(mesh.uvs.is_none());
+}
+
+#[test]
+fn merged_meshes_offset_their_indices() {
+  let path = write_fixture("triangle.gltf", TRIANGLE_GLTF);
+  let mut model = Model::load(&path).unwrap();
+  model.meshes.push(model.meshes[0].clone());
+
+  let data = model.to_mesh_data();
+  assert_eq!(data.vertices.len(), 6);
+  assert_eq!(data.indices, Some(Indices::U16(vec![0, 1, 2, 3, 4, 5])));
+}
+
+#[test]
+fn fit_to_unit_cube_centers_the_model() {
+  let path = write_fixture("triangle.gltf", TRIANGLE_GLTF);
+  let mut model = Model::load(&path).unwrap();
+  model.fit_to_unit_cube();
+
+  let (min, max) = model.bounds().unwrap();
+  assert_eq!(min, [-0.5, -0.5, 0.0]);
+  assert_eq!(max, [0.5, 0.5, 0.0]);
+}
+
+#[test]
+fn unknown_extensions_are_rejected() {
+  assert!(matches!(Model::load("model.fbx"), Err(LoadError::UnsupportedFile(_))));
+}
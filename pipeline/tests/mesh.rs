@@ -0,0 +1,17 @@
+use pipeline::{Indices, MeshData};
+
+#[test]
+fn indices_use_the_smallest_format() {
+  assert_eq!(Indices::new(vec![0, 1, 65535]), Indices::U16(vec![0, 1, 65535]));
+  assert_eq!(Indices::new(vec![0, 1, 65536]), Indices::U32(vec![0, 1, 65536]));
+  assert_eq!(Indices::U16(vec![0, 1, 2]).as_bytes().len(), 6);
+}
+
+#[test]
+fn primitives_only_index_their_own_vertices() {
+  for mesh in [MeshData::triangle(), MeshData::quad(), MeshData::polygon(6, 0.5)] {
+    let Some(Indices::U16(indices)) = &mesh.indices else { panic!("primitives use u16 indices") };
+    assert_eq!(indices.len() % 3, 0);
+    assert!(indices.iter().all(|&index| (index as usize) < mesh.vertices.len()));
+  }
+}
@@ -0,0 +1,77 @@
+use pipeline::{supported_sample_count, Config, HeadlessState};
+use renderer::testing::{Golden, Tolerance};
+
+fn golden() -> Golden {
+  Golden::new(env!("CARGO_MANIFEST_DIR"), env!("CARGO_TARGET_TMPDIR"))
+}
+
+async fn headless(args: &[&str]) -> HeadlessState {
+  let config = Config::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+  HeadlessState::with_config(128, 128, &config).await
+}
+
+#[tokio::test]
+async fn unsupported_sample_counts_fall_back() {
+  for requested in [1, 2, 4, 8] {
+    let state = headless(&["--msaa", &requested.to_string()]).await;
+    assert!(state.sample_count().is_power_of_two());
+    assert!(state.sample_count() <= requested, "{}x for {requested}x", state.sample_count());
+  }
+}
+
+#[tokio::test]
+async fn webgpu_devices_only_get_the_guaranteed_sample_counts() {
+  // a device without adapter specific format features is validated like WebGPU
+  let adapter = renderer::request_fallback_adapter().await.unwrap();
+  let (device, _queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+    label: None,
+    required_features: wgpu::Features::empty(),
+    required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
+  }, None).await.unwrap();
+  let formats = [wgpu::TextureFormat::Rgba8UnormSrgb, wgpu::TextureFormat::Depth24Plus];
+
+  if supported_sample_count(&adapter, &device, &formats, 4) != 4 {
+    eprintln!("adapter has no 4x MSAA, skipping");
+    return;
+  }
+  assert_eq!(supported_sample_count(&adapter, &device, &formats, 1), 1);
+  // never more than asked for
+  assert_eq!(supported_sample_count(&adapter, &device, &formats, 2), 1);
+  assert_eq!(supported_sample_count(&adapter, &device, &formats, 8), 4);
+}
+
+#[tokio::test]
+async fn msaa_only_changes_the_edges() {
+  let state = headless(&["--msaa=4"]).await;
+  if state.sample_count() == 1 {
+    eprintln!("adapter has no 4x MSAA, skipping");
+    return;
+  }
+  let pixels = state.render();
+  // one GL context at a time, the GLES fallback adapter can't switch between them
+  drop(state);
+  let aliased = headless(&[]).await.render();
+  assert_ne!(pixels, aliased, "resolved frame should have blended edge pixels");
+
+  // the interior still matches, the edges of a 128x128 triangle are a few hundred pixels
+  golden().assert_matches("triangle", 128, 128, &pixels, Tolerance {
+    per_channel: 2,
+    max_failing_pixels: 600,
+  });
+}
+
+#[tokio::test]
+async fn render_scale_keeps_the_output_size() {
+  for (scale, drawn) in [("2", 256), ("0.5", 64)] {
+    let state = headless(&["--render-scale", scale]).await;
+    assert_eq!(state.size(), (drawn, drawn));
+    let frame = state.capture_frame().unwrap();
+    assert_eq!((frame.width, frame.height), (128, 128));
+
+    // like MSAA, scaling only filters the edges
+    golden().assert_matches("triangle", 128, 128, &frame.pixels, Tolerance {
+      per_channel: 2,
+      max_failing_pixels: 1200,
+    });
+  }
+}
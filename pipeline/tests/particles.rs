@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use pipeline::{dispatch_size, particle_fountain, simulate_headless, HeadlessCompute, Particle, ParticleSimulation, SimParams};
+
+const STEPS: u32 = 120;
+
+fn assert_close(gpu: &[f32; 3], cpu: &[f32; 3], what: &str) {
+  for (g, c) in gpu.iter().zip(cpu) {
+    assert!((g - c).abs() < 1e-3, "{what}: gpu {gpu:?} cpu {cpu:?}");
+  }
+}
+
+#[test]
+fn dispatch_covers_every_item() {
+  assert_eq!(dispatch_size(0, 64), 0);
+  assert_eq!(dispatch_size(1, 64), 1);
+  assert_eq!(dispatch_size(64, 64), 1);
+  assert_eq!(dispatch_size(65, 64), 2);
+  assert_eq!(dispatch_size(1000, 64), 16);
+}
+
+#[test]
+fn particles_bounce_off_the_floor() {
+  let params = SimParams { gravity: [0.0; 3], dt: 0.1, ..Default::default() };
+  let mut particle = Particle::new([0.0, -0.9, 0.0], [0.0, -2.0, 0.0], 0.05);
+  particle.step(&params);
+
+  assert!((particle.position[1] + 0.95).abs() < 1e-6);
+  assert!((particle.velocity[1] - 1.6).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn gpu_steps_match_the_cpu_reference() {
+  let params = SimParams::default();
+  // not a multiple of the workgroup size, so the last group has idle invocations
+  let start = particle_fountain(100);
+  let gpu = match simulate_headless(params, &start, STEPS).await {
+    Ok(particles) => particles,
+    Err(e) => {
+      eprintln!("skipping: {e}");
+      return;
+    }
+  };
+
+  let mut cpu = start.clone();
+  for _ in 0..STEPS {
+    cpu.iter_mut().for_each(|particle| particle.step(&params));
+  }
+
+  assert_eq!(gpu.len(), cpu.len());
+  for (gpu, cpu) in gpu.iter().zip(&cpu) {
+    assert_close(&gpu.position, &cpu.position, "position");
+    assert_close(&gpu.velocity, &cpu.velocity, "velocity");
+    assert!((gpu.age - STEPS as f32 * params.dt).abs() < 1e-3);
+    for axis in 0..3 {
+      assert!(gpu.position[axis] >= params.bounds_min[axis] + gpu.radius - 1e-5);
+      assert!(gpu.position[axis] <= params.bounds_max[axis] - gpu.radius + 1e-5);
+    }
+  }
+}
+
+#[tokio::test]
+async fn instances_follow_the_particles() {
+  let compute = match HeadlessCompute::new().await {
+    Ok(compute) => compute,
+    Err(e) => {
+      eprintln!("skipping: {e}");
+      return;
+    }
+  };
+  let (device, queue) = (&compute.device, &compute.queue);
+
+  let mut simulation = ParticleSimulation::new(device, queue, SimParams::default(), &particle_fountain(10));
+  simulation.step(device, queue, 10);
+  // new params are used from the next step on
+  simulation.set_params(queue, SimParams { gravity: [0.0; 3], ..Default::default() });
+  simulation.step(device, queue, 1);
+
+  let particles = simulation.read_particles(device, queue).unwrap();
+  let instances = simulation.read_instances(device, queue).unwrap();
+  assert_eq!(instances.len(), 10);
+  for (particle, instance) in particles.iter().zip(&instances) {
+    let expected = particle.instance();
+    assert_close(&instance.position, &expected.position, "position");
+    assert_eq!(instance.scale, particle.radius);
+    assert_eq!(instance.rotation, [0.0, 0.0, 0.0, 1.0]);
+    for (a, b) in instance.color.iter().zip(&expected.color) {
+      assert!((a - b).abs() < 1e-3, "color {:?} != {:?}", instance.color, expected.color);
+    }
+  }
+}
+
+#[tokio::test]
+async fn advancing_runs_whole_steps_of_the_elapsed_time() {
+  let compute = match HeadlessCompute::new().await {
+    Ok(compute) => compute,
+    Err(e) => {
+      eprintln!("skipping: {e}");
+      return;
+    }
+  };
+  let (device, queue) = (&compute.device, &compute.queue);
+
+  let params = SimParams { dt: 0.02, ..Default::default() };
+  let mut simulation = ParticleSimulation::new(device, queue, params, &particle_fountain(10));
+  // what doesn't fill a step is carried over to the next call
+  let steps: Vec<_> = [5, 20, 50, 0].into_iter()
+    .map(|ms| simulation.advance(device, queue, Duration::from_millis(ms)))
+    .collect();
+  assert_eq!(steps, [0, 1, 2, 0]);
+
+  for particle in simulation.read_particles(device, queue).unwrap() {
+    assert!((particle.age - 3.0 * params.dt).abs() < 1e-5, "{}", particle.age);
+  }
+}
@@ -0,0 +1,135 @@
+use pipeline::{Config, FullscreenPass, GraphError, HeadlessState, RenderGraph};
+
+const SIZE: u32 = 64;
+
+async fn state(args: &[&str]) -> HeadlessState {
+  HeadlessState::with_config(SIZE, SIZE, &Config::parse(args.iter().map(|arg| arg.to_string())).unwrap()).await
+}
+
+fn pixel(pixels: &[u8], x: u32, y: u32) -> [u8; 4] {
+  let i = ((y * SIZE + x) * 4) as usize;
+  pixels[i..i + 4].try_into().unwrap()
+}
+
+// The largest difference between horizontal neighbours, large for sharp edges.
+fn sharpest_edge(pixels: &[u8]) -> u8 {
+  pixels.chunks(SIZE as usize * 4)
+    .flat_map(|row| row.windows(8).step_by(4).flat_map(|pair| (0..3).map(|c| pair[c].abs_diff(pair[c + 4]))))
+    .max()
+    .unwrap()
+}
+
+fn linear_to_srgb(x: f32) -> u8 {
+  let srgb = if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 };
+  (srgb * 255.0).round() as u8
+}
+
+fn aces(x: f32) -> f32 {
+  ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+}
+
+#[tokio::test]
+async fn grayscale_leaves_no_color() {
+  let pixels = state(&["--post=grayscale"]).await.render();
+
+  for pixel in pixels.chunks(4) {
+    assert!(pixel[0].abs_diff(pixel[1]) <= 1 && pixel[1].abs_diff(pixel[2]) <= 1, "{pixel:?}");
+  }
+  // the triangle is still there
+  assert_ne!(pixel(&pixels, SIZE / 2, SIZE / 2), pixel(&pixels, 0, 0));
+}
+
+#[tokio::test]
+async fn blur_softens_edges() {
+  let sharp = sharpest_edge(&state(&[]).await.render());
+  let blurred = sharpest_edge(&state(&["--post=blur"]).await.render());
+  assert!(blurred * 2 < sharp, "blurred {blurred}, sharp {sharp}");
+}
+
+#[tokio::test]
+async fn tonemap_applies_aces_with_exposure() {
+  let state = state(&["--post=tonemap"]).await;
+  // the clear color, in linear space
+  let expected = [0.1f32, 0.2, 0.3].map(|c| linear_to_srgb(aces(c)));
+  let corner = pixel(&state.render(), 0, 0);
+  for (actual, expected) in corner.iter().zip(expected) {
+    assert!(actual.abs_diff(expected) <= 2, "{corner:?} != {expected:?}");
+  }
+
+  assert!(state.post().unwrap().set_params(state.queue(), "tonemap0", [0.0; 4]));
+  assert!(!state.post().unwrap().set_params(state.queue(), "blur0", [0.0; 4]));
+  assert_eq!(pixel(&state.render(), 0, 0), [0, 0, 0, 255]);
+}
+
+#[tokio::test]
+async fn tonemap_chains_keep_the_range_above_one() {
+  let blurred = state(&["--post=blur"]).await.post().unwrap().target_format();
+  assert_eq!(blurred, HeadlessState::FORMAT);
+
+  let state = state(&["--post", "blur,tonemap", "--msaa", "4"]).await;
+  let post = state.post().unwrap();
+  assert_eq!(post.target_format(), wgpu::TextureFormat::Rgba16Float);
+  assert_eq!(post.format(), HeadlessState::FORMAT);
+  // blurring leaves the clear color as it was
+  let expected = [0.1f32, 0.2, 0.3].map(|c| linear_to_srgb(aces(c)));
+  let corner = pixel(&state.render(), 0, 0);
+  for (actual, expected) in corner.iter().zip(expected) {
+    assert!(actual.abs_diff(expected) <= 2, "{corner:?} != {expected:?}");
+  }
+}
+
+#[tokio::test]
+async fn effects_can_be_chained() {
+  let pixels = state(&["--post", "grayscale,blur,tonemap"]).await.render();
+  for pixel in pixels.chunks(4) {
+    assert!(pixel[0].abs_diff(pixel[1]) <= 1 && pixel[1].abs_diff(pixel[2]) <= 1, "{pixel:?}");
+  }
+}
+
+const COPY: &str = "
+@group(0) @binding(0) var input_sampler: sampler;
+@group(0) @binding(1) var input: texture_2d<f32>;
+
+@fragment
+fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4f {
+  return textureSample(input, input_sampler, uv);
+}
+";
+
+fn copy_pass<'a>(label: &'a str, inputs: &'a [&'a str], output: &'a str) -> FullscreenPass<'a> {
+  FullscreenPass { label, shader: COPY, inputs, output, params: None }
+}
+
+#[tokio::test]
+async fn passes_are_checked_when_added() {
+  let state = state(&[]).await;
+  let device = state.device();
+  let mut graph = RenderGraph::new(device, HeadlessState::FORMAT, SIZE, SIZE);
+  assert_eq!(
+    graph.add_pass(device, &copy_pass("early", &["copy"], "output")),
+    Err(GraphError::UnknownInput { pass: "early".to_string(), input: "copy".to_string() }),
+  );
+  assert_eq!(
+    graph.add_pass(device, &copy_pass("feedback", &["scene"], "scene")),
+    Err(GraphError::Feedback { pass: "feedback".to_string(), target: "scene".to_string() }),
+  );
+  assert!(matches!(
+    graph.add_pass(device, &FullscreenPass { shader: "fn fs_main(", ..copy_pass("broken", &["scene"], "copy") }),
+    Err(GraphError::Shader { .. }),
+  ));
+  // valid WGSL, but an integer output can't be written to the color target
+  let integer = "@fragment fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4i { return vec4i(1); }";
+  assert!(matches!(
+    graph.add_pass(device, &FullscreenPass { shader: integer, ..copy_pass("integer", &[], "copy") }),
+    Err(GraphError::Shader { ref pass, .. }) if pass == "integer",
+  ));
+
+  graph.add_pass(device, &copy_pass("copy", &["scene"], "copy")).unwrap();
+  graph.add_pass(device, &copy_pass("present", &["copy"], "output")).unwrap();
+  assert!(graph.view("copy").is_some());
+  assert!(graph.view("output").is_none());
+
+  // targets follow the size of the graph
+  graph.resize(device, SIZE * 2, SIZE);
+  assert!(graph.view("copy").is_some());
+}
@@ -0,0 +1,59 @@
+use pipeline::reflect::{self, LayoutMismatch, ReflectedLayout, VertexInput};
+
+const SHADER: &str = "
+struct VertexInput {
+  @location(0) position: vec3f,
+  @location(1) color: vec3f,
+};
+
+@vertex
+fn vs_main(in: VertexInput, @location(2) uv: vec2f, @builtin(vertex_index) index: u32) -> @builtin(position) vec4f {
+  return vec4f(in.position, 1.0);
+}
+";
+
+fn inputs() -> Vec<VertexInput> {
+  let module = reflect::parse_wgsl(SHADER).unwrap();
+  reflect::vertex_inputs(&module, "vs_main").unwrap()
+}
+
+#[test]
+fn reflects_struct_members_and_arguments() {
+  let locations: Vec<_> = inputs().into_iter().map(|input| (input.location, input.name, input.format)).collect();
+
+  assert_eq!(locations, vec![
+    (0, "in.position".to_string(), wgpu::VertexFormat::Float32x3),
+    (1, "in.color".to_string(), wgpu::VertexFormat::Float32x3),
+    (2, "uv".to_string(), wgpu::VertexFormat::Float32x2),
+  ]);
+}
+
+#[test]
+fn generated_layout_is_tightly_packed() {
+  let layout = ReflectedLayout::from_inputs(&inputs());
+
+  assert_eq!(layout.array_stride, 32);
+  assert_eq!(layout.attributes.iter().map(|a| a.offset).collect::<Vec<_>>(), vec![0, 12, 24]);
+  assert_eq!(reflect::check_layout(&inputs(), &[layout.desc()]), Ok(()));
+}
+
+#[test]
+fn reports_mismatched_locations_and_formats() {
+  let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4, 3 => Unorm8x4];
+  let layout = wgpu::VertexBufferLayout {
+    array_stride: 32,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &attributes,
+  };
+
+  assert_eq!(reflect::check_layout(&inputs(), &[layout]), Err(vec![
+    LayoutMismatch::Format {
+      location: 1,
+      name: "in.color".to_string(),
+      shader: wgpu::VertexFormat::Float32x3,
+      buffer: wgpu::VertexFormat::Float32x4,
+    },
+    LayoutMismatch::MissingLocation { location: 2, name: "uv".to_string(), format: wgpu::VertexFormat::Float32x2 },
+    LayoutMismatch::UnusedLocation { location: 3, format: wgpu::VertexFormat::Unorm8x4 },
+  ]));
+}
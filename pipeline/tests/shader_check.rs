@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+#[test]
+fn crate_shaders_pass_every_profile() {
+  let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+  assert!(pipeline::shader_check::check(&[src]));
+}
+
+#[test]
+fn compute_shaders_fail_on_webgl2() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("shader_check");
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("double.wgsl"), "
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3u) {
+  data[id.x] = data[id.x] * 2.0;
+}
+").unwrap();
+
+  assert!(!pipeline::shader_check::check(&[dir]));
+}
+
+#[test]
+fn skipped_profiles_are_not_checked() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("shader_check_skip");
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("double.wgsl"), "
+// check-shaders: skip WebGL2
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3u) {
+  data[id.x] = data[id.x] * 2.0;
+}
+").unwrap();
+
+  assert!(pipeline::shader_check::check(&[dir]));
+}
+
+#[test]
+fn shaders_of_every_crate_pass_every_profile() {
+  let dirs = pipeline::shader_check::default_dirs();
+  assert!(dirs.iter().any(|dir| dir.ends_with("renderer/src")));
+  assert!(pipeline::shader_check::check(&dirs));
+}
+
+#[test]
+fn huge_workgroup_sizes_fail() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("shader_check_overflow");
+  std::fs::create_dir_all(&dir).unwrap();
+  // 65536 * 65536 doesn't fit in a u32
+  std::fs::write(dir.join("huge.wgsl"), "
+// check-shaders: skip WebGL2
+@compute @workgroup_size(65536, 65536, 1)
+fn main() {}
+").unwrap();
+
+  assert!(!pipeline::shader_check::check(&[dir]));
+}
@@ -0,0 +1,92 @@
+use pipeline::{Config, HeadlessState, Indices, MeshData, Texture, TextureError, TexturedVertex};
+
+const SIZE: u32 = 64;
+
+// A quad covering the whole view at the default camera, with the texture repeated `repeat` times.
+fn fullscreen_quad(repeat: f32) -> MeshData<TexturedVertex> {
+  MeshData {
+    vertices: vec![
+      TexturedVertex { position: [-1.0, -1.0, 0.0], uv: [0.0, repeat] },
+      TexturedVertex { position: [ 1.0, -1.0, 0.0], uv: [repeat, repeat] },
+      TexturedVertex { position: [ 1.0,  1.0, 0.0], uv: [repeat, 0.0] },
+      TexturedVertex { position: [-1.0,  1.0, 0.0], uv: [0.0, 0.0] },
+    ],
+    indices: Some(Indices::U16(vec![0, 1, 2, 0, 2, 3])),
+  }
+}
+
+fn pixel(pixels: &[u8], x: u32, y: u32) -> [u8; 4] {
+  let i = ((y * SIZE + x) * 4) as usize;
+  pixels[i..i + 4].try_into().unwrap()
+}
+
+async fn headless() -> HeadlessState {
+  HeadlessState::with_config(SIZE, SIZE, &Config::parse(["--depth=none".to_string()]).unwrap()).await
+}
+
+#[tokio::test]
+async fn png_texture_is_sampled_top_row_first() {
+  // red, green / blue, white quadrants, one texel per pixel of the view
+  let quadrant = |x: u32, y: u32| match (x < SIZE / 2, y < SIZE / 2) {
+    (true, true) => [255, 0, 0, 255],
+    (false, true) => [0, 255, 0, 255],
+    (true, false) => [0, 0, 255, 255],
+    (false, false) => [255, 255, 255, 255],
+  };
+  let rgba: Vec<u8> = (0..SIZE * SIZE).flat_map(|i| quadrant(i % SIZE, i / SIZE)).collect();
+
+  let mut png = Vec::new();
+  {
+    let mut encoder = png::Encoder::new(&mut png, SIZE, SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(&rgba).unwrap();
+  }
+
+  let mut state = headless().await;
+  let texture = Texture::from_bytes(state.device(), state.queue(), &png, "quadrants").unwrap();
+  assert_eq!(texture.mip_level_count(), 7);
+  state.set_textured_mesh(&fullscreen_quad(1.0), texture);
+
+  let pixels = state.render();
+  for (x, y) in [(8, 8), (SIZE - 8, 8), (8, SIZE - 8), (SIZE - 8, SIZE - 8)] {
+    let actual = pixel(&pixels, x, y);
+    let expected = quadrant(x, y);
+    assert!(actual.iter().zip(expected).all(|(&a, e)| a.abs_diff(e) <= 2), "({x}, {y}): {actual:?} != {expected:?}");
+  }
+}
+
+#[tokio::test]
+async fn minified_texture_samples_the_generated_mips() {
+  // black and white checkerboard, tiled so each texel is much smaller than a pixel
+  let pixels: Vec<u8> = (0..16 * 16)
+    .flat_map(|i| if (i % 16 + i / 16) % 2 == 0 { [0, 0, 0, 255] } else { [255, 255, 255, 255] })
+    .collect();
+
+  let mut state = headless().await;
+  let texture = Texture::from_rgba8(state.device(), state.queue(), 16, 16, &pixels, "checker").unwrap();
+  assert_eq!(texture.mip_level_count(), 5);
+  state.set_textured_mesh(&fullscreen_quad(16.0), texture);
+
+  // averaged in linear space, so 50% grey comes out as sRGB 188 rather than 128
+  let [r, g, b, _] = pixel(&state.render(), SIZE / 2, SIZE / 2);
+  for channel in [r, g, b] {
+    assert!((180..=196).contains(&channel), "{:?}", [r, g, b]);
+  }
+}
+
+#[test]
+fn undecodable_bytes_are_an_error() {
+  let state = pollster::block_on(headless());
+  assert!(Texture::from_bytes(state.device(), state.queue(), b"not an image", "junk").is_err());
+  assert!(Texture::from_rgba8(state.device(), state.queue(), 2, 2, &[0; 4], "short").is_err());
+}
+
+#[test]
+fn textures_larger_than_the_device_allows_are_an_error() {
+  let state = pollster::block_on(headless());
+  let max = state.device().limits().max_texture_dimension_2d;
+  let pixels = vec![0; (max as usize + 1) * 4];
+  let result = Texture::from_rgba8(state.device(), state.queue(), max + 1, 1, &pixels, "wide");
+  assert!(matches!(result, Err(TextureError::TooLarge { width, height: 1, .. }) if width == max + 1));
+}
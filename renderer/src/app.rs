@@ -0,0 +1,183 @@
+use std::{sync::Arc, time::Duration};
+
+use winit::{
+  dpi::Size, event::*, event_loop::{ControlFlow, EventLoop}, window::WindowBuilder
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::{ColorSpace, FrameLimiter, Instant, RecoveryPolicy, Renderer, Scene};
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// How `run` sets up the window.
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+  pub title: String,
+  // the initial size of the window, or of the canvas on the web
+  pub size: Option<Size>,
+  pub color_space: ColorSpace,
+  pub recovery: RecoveryPolicy,
+  // scenes draw at this multiple of the window size, see `SurfaceSize`
+  pub render_scale: f32,
+  // falls back to a supported mode, see `select_present_mode`
+  pub present_mode: wgpu::PresentMode,
+  pub frame_latency: u32,
+  // wait between frames instead of drawing the next one right away, see `FrameLimiter`
+  pub target_fps: Option<f64>,
+  // log the frame and pass times every second and show the frame rate in the title
+  pub profile: bool,
+}
+
+impl Default for RunOptions {
+  fn default() -> Self {
+    Self {
+      title: "wgpu".to_string(),
+      size: None,
+      color_space: ColorSpace::default(),
+      recovery: RecoveryPolicy::default(),
+      render_scale: 1.0,
+      present_mode: wgpu::PresentMode::Fifo,
+      frame_latency: 2,
+      target_fps: None,
+      profile: false,
+    }
+  }
+}
+
+// Logs to the console on the web and to stderr elsewhere, at info level unless `RUST_LOG` says otherwise.
+pub fn init_logger() {
+  cfg_if::cfg_if! {
+    if #[cfg(target_arch="wasm32")] {
+      std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+      console_log::init_with_level(log::Level::Info).expect("Couldn't initialize logger");
+    } else {
+      env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    }
+  }
+}
+
+// Opens a window, builds the scene with `build` once the renderer is ready and draws it
+// until the window is closed. After the device was lost and recreated the scene recreates
+// its GPU objects, or is built again if it can't, see `Scene::recreate`. Pressing P saves the current frame as a png in the working directory.
+pub async fn run<S, F>(options: RunOptions, mut build: F)
+where
+  S: Scene + 'static,
+  F: FnMut(&Renderer) -> S + 'static,
+{
+  let event_loop = EventLoop::new().unwrap();
+
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  window.set_title(&options.title);
+  if let Some(size) = options.size {
+    let _ = window.request_inner_size(size);
+  }
+
+  // add canvas to the HTML document that we will host our application
+  #[cfg(target_arch = "wasm32")]
+  {
+    log::info!("[run]: initializing html canvas");
+    // Winit prevents sizing with CSS, so `size` is set through the window above.
+    use winit::platform::web::WindowExtWebSys;
+    web_sys::window()
+      .and_then(|win| win.document())
+      .and_then(|doc| {
+        let dst = doc.get_element_by_id("wgpu-container")?;
+        let canvas = window.canvas().unwrap();
+        let el = web_sys::HtmlCanvasElement::from(canvas);
+        dst.append_child(&el).ok()?;
+        Some(())
+      })
+      .expect("couldn't add canvas to document");
+  }
+
+  let mut renderer = Renderer::new(Arc::new(window), options.color_space, options.recovery).await
+    .unwrap_or_else(|e| panic!("[run]: {e}"));
+  renderer.set_render_scale(options.render_scale);
+  renderer.set_present_mode(options.present_mode);
+  renderer.set_frame_latency(options.frame_latency);
+  let mut limiter = FrameLimiter::new(options.target_fps);
+  let mut next_report = Instant::now() + REPORT_INTERVAL;
+  let mut scene = build(&renderer);
+
+  event_loop.set_control_flow(ControlFlow::Wait);
+
+  let _ = event_loop.run(move |event, control_flow| {
+    match event {
+      // the backoff after a failed frame is over, or the next frame is due
+      Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+        control_flow.set_control_flow(ControlFlow::Wait);
+        renderer.window().request_redraw();
+      }
+      Event::WindowEvent {
+        ref event,
+        window_id,
+      } if window_id == renderer.window().id() => {
+        match event {
+          WindowEvent::CloseRequested => control_flow.exit(),
+          WindowEvent::Resized(new_size) => {
+            if renderer.resize(*new_size) {
+              let (width, height) = renderer.size();
+              scene.resize(renderer.gpu(), width, height);
+            }
+            renderer.window().request_redraw();
+          }
+          WindowEvent::ScaleFactorChanged { scale_factor, .. } => renderer.set_scale_factor(*scale_factor),
+          #[cfg(not(target_arch = "wasm32"))]
+          WindowEvent::KeyboardInput {
+            event: KeyEvent {
+              physical_key: PhysicalKey::Code(KeyCode::KeyP),
+              state: ElementState::Pressed,
+              repeat: false,
+              ..
+            },
+            ..
+          } => renderer.save_capture(&scene),
+          WindowEvent::RedrawRequested => {
+            if renderer.device().is_lost() {
+              // nothing on the web can wait for the new device here
+              #[cfg(target_arch = "wasm32")]
+              let recovered = Err(());
+              #[cfg(not(target_arch = "wasm32"))]
+              let recovered = pollster::block_on(renderer.recover_device())
+                .map_err(|e| log::error!("[run]: {}", e));
+              match recovered {
+                Ok(true) => {
+                  if !scene.recreate(renderer.gpu()) {
+                    scene = build(&renderer);
+                  }
+                }
+                Ok(false) => (),
+                Err(_) => {
+                  control_flow.exit();
+                  return;
+                }
+              }
+            }
+            scene.update(renderer.gpu());
+            // the renderer recovers what it can, what is left is fatal
+            if renderer.render(&mut scene).is_err() {
+              control_flow.exit();
+              return;
+            }
+            if options.profile && Instant::now() >= next_report {
+              log::info!("[profiler]: {}", renderer.profiler());
+              let fps = renderer.profiler().stats().fps;
+              renderer.window().set_title(&format!("{} - {:.0} fps", options.title, fps));
+              next_report = Instant::now() + REPORT_INTERVAL;
+            }
+            match renderer.retry_at().or_else(|| limiter.frame_done(Instant::now())) {
+              Some(at) => control_flow.set_control_flow(ControlFlow::WaitUntil(at)),
+              None => renderer.window().request_redraw(),
+            }
+          }
+          _ => {
+            scene.input(event);
+          }
+        }
+      }
+      _ => (),
+    }
+  });
+}
@@ -0,0 +1,227 @@
+use std::{fmt, io, path::Path};
+
+use crate::{scaled::ScaledTarget, ColorPolicy, Frame, Gpu, Scene, SurfaceSize};
+
+// A frame read back from the GPU as tightly packed, sRGB encoded RGBA8 rows, top row first.
+pub struct CapturedFrame {
+  pub width: u32,
+  pub height: u32,
+  pub pixels: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+  UnsupportedFormat(wgpu::TextureFormat),
+  Map(wgpu::BufferAsyncError),
+  Png(png::EncodingError),
+  Io(io::Error),
+}
+
+impl fmt::Display for CaptureError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CaptureError::UnsupportedFormat(format) => write!(f, "can't capture frames in {format:?}"),
+      CaptureError::Map(e) => write!(f, "failed to map readback buffer: {e}"),
+      CaptureError::Png(e) => write!(f, "failed to encode png: {e}"),
+      CaptureError::Io(e) => write!(f, "failed to write capture: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<png::EncodingError> for CaptureError {
+  fn from(e: png::EncodingError) -> Self {
+    CaptureError::Png(e)
+  }
+}
+
+impl From<io::Error> for CaptureError {
+  fn from(e: io::Error) -> Self {
+    CaptureError::Io(e)
+  }
+}
+
+impl CapturedFrame {
+  pub fn to_png(&self) -> Result<Vec<u8>, CaptureError> {
+    let mut bytes = Vec::new();
+    {
+      let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+      encoder.set_color(png::ColorType::Rgba);
+      encoder.set_depth(png::BitDepth::Eight);
+      encoder.write_header()?.write_image_data(&self.pixels)?;
+    }
+    Ok(bytes)
+  }
+
+  pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), CaptureError> {
+    std::fs::write(path, self.to_png()?)?;
+    Ok(())
+  }
+}
+
+// Whether red and blue have to be swapped, for the formats `read_texture` can read.
+fn readable(format: wgpu::TextureFormat) -> Option<bool> {
+  match format {
+    wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Some(false),
+    wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Some(true),
+    _ => None,
+  }
+}
+
+// Whether presented frames can be copied straight out of a surface of `format`: the surface
+// has to allow `COPY_SRC` and `read_texture` has to understand the format.
+pub fn can_copy_surface(caps: &wgpu::SurfaceCapabilities, format: wgpu::TextureFormat) -> bool {
+  caps.usages.contains(wgpu::TextureUsages::COPY_SRC) && readable(format).is_some()
+}
+
+// Texture for re-rendering the current frame so it can be copied out, for surfaces that
+// can't be copied from and for headless rendering.
+pub fn create_capture_texture(
+  device: &wgpu::Device,
+  width: u32,
+  height: u32,
+  format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+  device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Capture Target"),
+    size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    view_formats: &[],
+  })
+}
+
+// Appends a copy of `texture` to `encoder`, submits it and waits for the result. `policy` is
+// what `texture` was rendered with: its values are only sRGB encoded for the PNG if neither
+// the format nor the final pass did already.
+pub fn read_texture(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  mut encoder: wgpu::CommandEncoder,
+  texture: &wgpu::Texture,
+  policy: &ColorPolicy,
+) -> Result<CapturedFrame, CaptureError> {
+  let format = texture.format();
+  let swap_red_blue = readable(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+  let is_srgb = policy.output_is_srgb();
+
+  let width = texture.width();
+  let height = texture.height();
+
+  // rows of a texture-to-buffer copy must be aligned to COPY_BYTES_PER_ROW_ALIGNMENT
+  let unpadded_bytes_per_row = width * 4;
+  let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+  let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+  let readback = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Capture Readback Buffer"),
+    size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+
+  encoder.copy_texture_to_buffer(
+    wgpu::ImageCopyTexture {
+      texture,
+      mip_level: 0,
+      origin: wgpu::Origin3d::ZERO,
+      aspect: wgpu::TextureAspect::All,
+    },
+    wgpu::ImageCopyBuffer {
+      buffer: &readback,
+      layout: wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(padded_bytes_per_row),
+        rows_per_image: Some(height),
+      },
+    },
+    texture.size(),
+  );
+
+  queue.submit(std::iter::once(encoder.finish()));
+
+  let slice = readback.slice(..);
+  let (sender, receiver) = std::sync::mpsc::channel();
+  slice.map_async(wgpu::MapMode::Read, move |result| {
+    let _ = sender.send(result);
+  });
+  device.poll(wgpu::Maintain::Wait);
+  receiver.recv().unwrap().map_err(CaptureError::Map)?;
+
+  let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+  {
+    let data = slice.get_mapped_range();
+    for row in data.chunks(padded_bytes_per_row as usize) {
+      pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+  }
+  readback.unmap();
+
+  for pixel in pixels.chunks_mut(4) {
+    if swap_red_blue {
+      pixel.swap(0, 2);
+    }
+    // png expects sRGB, a linear target has to be encoded first
+    if !is_srgb {
+      for channel in &mut pixel[..3] {
+        *channel = linear_to_srgb(*channel);
+      }
+    }
+  }
+
+  Ok(CapturedFrame { width, height, pixels })
+}
+
+// Renders `scene` at the render size of `size` into a new texture of the policy's render
+// format with the physical size and reads it back.
+pub(crate) fn capture_scene(
+  gpu: &Gpu,
+  scene: &dyn Scene,
+  policy: &ColorPolicy,
+  size: SurfaceSize,
+) -> Result<CapturedFrame, CaptureError> {
+  let format = policy.render_format;
+  let physical = size.physical();
+  let texture = create_capture_texture(&gpu.device, physical.width, physical.height, format);
+  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+  let mut encoder = gpu.device.create_command_encoder(
+    &wgpu::CommandEncoderDescriptor {
+      label: Some("Capture Encoder")
+    }
+  );
+
+  let (width, height) = size.render_size();
+  if size.is_scaled() {
+    let scaled = ScaledTarget::new(&gpu.device, format, width, height);
+    scene.render(&Frame { gpu, view: scaled.view(), format, width, height, timer: None }, &mut encoder);
+    scaled.encode(&mut encoder, &view, None);
+  } else {
+    scene.render(&Frame { gpu, view: &view, format, width, height, timer: None }, &mut encoder);
+  }
+
+  read_texture(&gpu.device, &gpu.queue, encoder, &texture, policy)
+}
+
+// Where P saves a capture, in the working directory.
+pub(crate) fn capture_path() -> String {
+  let millis = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_millis())
+    .unwrap_or_default();
+  format!("capture-{millis}.png")
+}
+
+fn linear_to_srgb(value: u8) -> u8 {
+  let linear = value as f32 / 255.0;
+  let srgb = if linear <= 0.003_130_8 {
+    linear * 12.92
+  } else {
+    1.055 * linear.powf(1.0 / 2.4) - 0.055
+  };
+  (srgb * 255.0).round() as u8
+}
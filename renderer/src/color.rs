@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+// How the values written to the surface are encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+  // sRGB encoded, what displays expect for 8 bit surfaces
+  #[default]
+  Srgb,
+  // written as is, e.g. for surfaces the compositor treats as linear
+  Linear,
+}
+
+impl FromStr for ColorSpace {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "srgb" => Ok(ColorSpace::Srgb),
+      "linear" => Ok(ColorSpace::Linear),
+      _ => Err(format!("unknown color space `{s}`, expected srgb or linear")),
+    }
+  }
+}
+
+// What the platform allows when configuring a surface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SurfaceSupport {
+  // Chrome's WebGPU rejects sRGB swap chain formats:
+  // unsupported swap chain format "xxxx8unorm-srgb"
+  pub srgb_formats: bool,
+  // WebGL2 and some GL drivers can't view the surface in another format:
+  // Downlevel flags DownlevelFlags(SURFACE_VIEW_FORMATS) are required but not supported on the device.
+  pub view_formats: bool,
+}
+
+impl SurfaceSupport {
+  pub fn new(adapter: &wgpu::Adapter) -> Self {
+    Self {
+      srgb_formats: !cfg!(all(target_arch = "wasm32", not(feature = "webgl"))),
+      view_formats: adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS),
+    }
+  }
+}
+
+// Every sRGB/linear decision for the surface in one place: the format it is configured
+// with, the format of the view the final pass renders into, and whether that pass has to
+// encode sRGB itself because neither the surface nor a view of it can.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorPolicy {
+  pub color_space: ColorSpace,
+  pub surface_format: wgpu::TextureFormat,
+  pub view_formats: Vec<wgpu::TextureFormat>,
+  pub render_format: wgpu::TextureFormat,
+  pub encode_srgb: bool,
+}
+
+impl ColorPolicy {
+  // `formats` are the surface formats in the adapter's order of preference.
+  pub fn new(formats: &[wgpu::TextureFormat], color_space: ColorSpace, support: SurfaceSupport) -> Self {
+    let preferred = formats[0];
+    let wants_srgb = color_space == ColorSpace::Srgb;
+
+    // prefer a format that already matches the color space
+    let surface_format = formats.iter().copied()
+      .filter(|format| support.srgb_formats || !format.is_srgb())
+      .find(|format| format.is_srgb() == wants_srgb)
+      .unwrap_or(if support.srgb_formats { preferred } else { preferred.remove_srgb_suffix() });
+
+    let view_formats = if support.view_formats {
+      let mut formats = vec![surface_format.add_srgb_suffix(), surface_format.remove_srgb_suffix()];
+      formats.dedup();
+      formats
+    } else {
+      vec![]
+    };
+
+    let render_format = if wants_srgb { surface_format.add_srgb_suffix() } else { surface_format.remove_srgb_suffix() };
+    let render_format = if view_formats.contains(&render_format) { render_format } else { surface_format };
+    if !wants_srgb && render_format.is_srgb() {
+      log::warn!("[color]: no linear view of {:?}, the output will be sRGB encoded", surface_format);
+    }
+
+    Self {
+      color_space,
+      surface_format,
+      view_formats,
+      render_format,
+      encode_srgb: wants_srgb && !render_format.is_srgb(),
+    }
+  }
+
+  // For rendering into a texture of `format` that is used as is, e.g. offscreen.
+  pub fn for_format(format: wgpu::TextureFormat, color_space: ColorSpace) -> Self {
+    Self {
+      color_space,
+      surface_format: format,
+      view_formats: vec![],
+      render_format: format,
+      encode_srgb: color_space == ColorSpace::Srgb && !format.is_srgb(),
+    }
+  }
+
+  // Whether what ends up in the output texture is sRGB encoded, by an sRGB view of it or by
+  // the final pass, rather than linear. Decides how frames read back from it are treated.
+  pub fn output_is_srgb(&self) -> bool {
+    self.render_format.is_srgb() || self.encode_srgb
+  }
+}
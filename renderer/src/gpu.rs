@@ -0,0 +1,101 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum GpuError {
+  NoAdapter,
+  RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for GpuError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GpuError::NoAdapter => write!(f, "no suitable adapter found"),
+      GpuError::RequestDevice(e) => write!(f, "failed to request a device: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for GpuError {}
+
+// The adapter and the device opened on it, shared by the windowed and the headless renderer.
+pub struct Gpu {
+  pub adapter: wgpu::Adapter,
+  pub device: wgpu::Device,
+  pub queue: wgpu::Queue,
+}
+
+impl Gpu {
+  // Picks an adapter that can present to `compatible_surface` and opens a device on it with
+  // `default_limits`. Without a surface any adapter will do.
+  pub async fn new(instance: &wgpu::Instance, compatible_surface: Option<&wgpu::Surface<'_>>) -> Result<Self, GpuError> {
+    let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+      power_preference: wgpu::PowerPreference::default(),
+      compatible_surface,
+      force_fallback_adapter: false,
+    }).await.ok_or(GpuError::NoAdapter)?;
+
+    Self::with_adapter(adapter, "Device", Self::default_limits()).await
+  }
+
+  // A device for rendering offscreen, on the software adapter if there is one.
+  pub async fn headless(limits: wgpu::Limits) -> Result<Self, GpuError> {
+    let adapter = request_fallback_adapter().await.ok_or(GpuError::NoAdapter)?;
+    Self::with_adapter(adapter, "Headless Device", limits).await
+  }
+
+  // `limits` are raised to the texture sizes the adapter supports.
+  pub async fn with_adapter(adapter: wgpu::Adapter, label: &str, limits: wgpu::Limits) -> Result<Self, GpuError> {
+    log::info!("[gpu]: adapter_info {:?}", adapter.get_info());
+
+    let (device, queue) = request_device(&adapter, label, &limits).await?;
+    Ok(Self { adapter, device, queue })
+  }
+
+  pub fn default_limits() -> wgpu::Limits {
+    cfg_if::cfg_if! {
+      if #[cfg(all(target_arch = "wasm32", feature = "webgl"))] {
+        wgpu::Limits::downlevel_webgl2_defaults()
+      } else {
+        wgpu::Limits::default()
+      }
+    }
+  }
+
+  pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+    self.adapter.get_info()
+  }
+}
+
+pub(crate) async fn request_device(
+  adapter: &wgpu::Adapter, label: &str, limits: &wgpu::Limits,
+) -> Result<(wgpu::Device, wgpu::Queue), GpuError> {
+  adapter.request_device(
+    &wgpu::DeviceDescriptor {
+      label: Some(label),
+      // timestamps for the profiler, and the sample counts the adapter supports beyond
+      // WebGPU's, where there are any
+      required_features: adapter.features()
+        & (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
+      required_limits: limits.clone().using_resolution(adapter.limits()),
+    },
+    None
+  ).await.map_err(GpuError::RequestDevice)
+}
+
+// Prefers the software adapter so the output does not depend on the host GPU,
+// but takes whatever is available if there is none.
+pub async fn request_fallback_adapter() -> Option<wgpu::Adapter> {
+  let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+    backends: wgpu::Backends::all(),
+    ..Default::default()
+  });
+
+  match instance.request_adapter(&wgpu::RequestAdapterOptions {
+    power_preference: wgpu::PowerPreference::default(),
+    compatible_surface: None,
+    force_fallback_adapter: true,
+  }).await {
+    Some(adapter) => Some(adapter),
+    None => instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await,
+  }
+}
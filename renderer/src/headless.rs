@@ -0,0 +1,74 @@
+use crate::{
+  capture::{self, CaptureError, CapturedFrame},
+  ColorPolicy, ColorSpace, DeviceError, DeviceLifecycle, Gpu, Scene, SurfaceSize,
+};
+
+use winit::dpi::PhysicalSize;
+
+// Renders scenes into an offscreen texture instead of a window surface, so they can run
+// in CI and on machines without a display or GPU.
+pub struct HeadlessRenderer {
+  device: DeviceLifecycle,
+  size: SurfaceSize,
+  policy: ColorPolicy,
+}
+
+impl HeadlessRenderer {
+  pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+  pub fn new(gpu: Gpu, width: u32, height: u32) -> Self {
+    let size = SurfaceSize::new(PhysicalSize::new(width, height), 1.0)
+      .with_max_dimension(gpu.device.limits().max_texture_dimension_2d);
+    // a lost device is requested again with the limits it had
+    let limits = gpu.device.limits();
+    Self {
+      device: DeviceLifecycle::new(gpu, "Headless Device", limits),
+      size,
+      policy: ColorPolicy::for_format(Self::FORMAT, ColorSpace::Srgb),
+    }
+  }
+
+  pub fn gpu(&self) -> &Gpu {
+    self.device.gpu()
+  }
+
+  pub fn device(&self) -> &DeviceLifecycle {
+    &self.device
+  }
+
+  // Recreates a lost device, follow `Ok(true)` with `Scene::recreate`.
+  pub fn recover_device(&mut self) -> Result<bool, DeviceError> {
+    pollster::block_on(self.device.recover())
+  }
+
+  // Rendering into `FORMAT` as is, sRGB encoded by the format.
+  pub fn policy(&self) -> &ColorPolicy {
+    &self.policy
+  }
+
+  // The size scenes draw at.
+  pub fn size(&self) -> (u32, u32) {
+    self.size.render_size()
+  }
+
+  pub fn surface_size(&self) -> &SurfaceSize {
+    &self.size
+  }
+
+  // The size of the captured frames. Follow with `Scene::resize` so the scene's own targets
+  // match.
+  pub fn resize(&mut self, width: u32, height: u32) {
+    self.size.resize(PhysicalSize::new(width, height));
+  }
+
+  // Draws scenes larger or smaller than the captured frames, see `SurfaceSize`. Follow with
+  // `Scene::resize` as well.
+  pub fn set_render_scale(&mut self, render_scale: f32) {
+    self.size.set_render_scale(render_scale);
+  }
+
+  // Renders one frame of `scene` in `FORMAT` and reads it back.
+  pub fn capture(&self, scene: &dyn Scene) -> Result<CapturedFrame, CaptureError> {
+    capture::capture_scene(self.gpu(), scene, &self.policy, self.size)
+  }
+}
@@ -0,0 +1,53 @@
+// The window, surface and event loop shared by the examples. Each example is a `Scene`
+// that `run` draws every frame; `HeadlessRenderer` draws the same scenes offscreen.
+
+mod app;
+pub use app::{init_logger, run, RunOptions};
+
+mod color;
+pub use color::{ColorPolicy, ColorSpace, SurfaceSupport};
+
+mod gpu;
+pub use gpu::{request_fallback_adapter, Gpu, GpuError};
+
+mod lifecycle;
+pub use lifecycle::{validate, DeviceError, DeviceLifecycle};
+
+mod pacing;
+pub use pacing::{frame_interval, parse_present_mode, select_present_mode, FrameLimiter};
+
+mod profiler;
+pub use profiler::{pass_timings, FrameStats, FrameTimer, GpuTimer, PassTiming, Profiler};
+
+mod recovery;
+pub use recovery::{Acquired, FrameSurface, RecoveryPolicy, SurfaceEvent, SurfaceRecovery};
+
+mod scaled;
+
+mod scene;
+pub use scene::{ClearNode, Frame, NodeScene, RenderNode, Scene};
+
+mod size;
+pub use size::SurfaceSize;
+
+mod window;
+pub use window::Renderer;
+
+// `std::time::Instant` panics on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capture;
+#[cfg(not(target_arch = "wasm32"))]
+pub use capture::{CaptureError, CapturedFrame};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::HeadlessRenderer;
@@ -0,0 +1,140 @@
+use std::{
+  fmt, sync::{Arc, Mutex}
+};
+
+use crate::{gpu::request_device, Gpu};
+
+// how many uncaptured errors are kept for `take_errors`, older ones are only logged
+const MAX_ERRORS: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceError {
+  // creating `label` failed validation, see `validate`
+  Validation { label: String, message: String },
+  // reported by the device outside of an error scope
+  Uncaptured(String),
+  // the device could not be opened again after it was lost
+  Recreate(String),
+}
+
+impl fmt::Display for DeviceError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DeviceError::Validation { label, message } => write!(f, "creating {label} failed: {message}"),
+      DeviceError::Uncaptured(message) => write!(f, "uncaptured error: {message}"),
+      DeviceError::Recreate(message) => write!(f, "failed to recreate the device: {message}"),
+    }
+  }
+}
+
+impl std::error::Error for DeviceError {}
+
+// What the callbacks installed on the device report back.
+#[derive(Default)]
+struct Watch {
+  lost: Option<String>,
+  errors: Vec<DeviceError>,
+}
+
+// Creates something with `create` inside a validation error scope, so a rejected pipeline or
+// resource comes back as an error instead of reaching the uncaptured handler.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn validate<T>(device: &wgpu::Device, label: &str, create: impl FnOnce() -> T) -> Result<T, DeviceError> {
+  device.push_error_scope(wgpu::ErrorFilter::Validation);
+  let value = create();
+  match pollster::block_on(device.pop_error_scope()) {
+    Some(e) => {
+      log::error!("[device]: {} failed validation", label);
+      Err(DeviceError::Validation { label: label.to_string(), message: e.to_string() })
+    }
+    None => Ok(value),
+  }
+}
+
+// Nothing on the web can wait for the error scope here, errors reach the uncaptured handler.
+#[cfg(target_arch = "wasm32")]
+pub fn validate<T>(_device: &wgpu::Device, _label: &str, create: impl FnOnce() -> T) -> Result<T, DeviceError> {
+  Ok(create())
+}
+
+// Owns the device. Uncaptured errors are logged instead of panicking, and once the device is
+// lost `recover` opens a new one on the same adapter. What was created on the lost device is
+// gone with it and has to be created again by its owner, see `Scene::recreate`.
+pub struct DeviceLifecycle {
+  gpu: Gpu,
+  label: String,
+  limits: wgpu::Limits,
+  watch: Arc<Mutex<Watch>>,
+  generation: u32,
+}
+
+impl DeviceLifecycle {
+  // `label` and `limits` are what the device is requested with again after a loss.
+  pub fn new(gpu: Gpu, label: &str, limits: wgpu::Limits) -> Self {
+    let watch = watch(&gpu.device);
+    Self { gpu, label: label.to_string(), limits, watch, generation: 0 }
+  }
+
+  pub fn gpu(&self) -> &Gpu {
+    &self.gpu
+  }
+
+  // How often the device has been recreated.
+  pub fn generation(&self) -> u32 {
+    self.generation
+  }
+
+  pub fn is_lost(&self) -> bool {
+    self.watch.lock().unwrap().lost.is_some()
+  }
+
+  // The uncaptured errors since the last call, at most the latest `MAX_ERRORS`.
+  pub fn take_errors(&self) -> Vec<DeviceError> {
+    std::mem::take(&mut self.watch.lock().unwrap().errors)
+  }
+
+  // Opens a new device if the device was lost. Returns whether it did.
+  pub async fn recover(&mut self) -> Result<bool, DeviceError> {
+    let Some(reason) = self.watch.lock().unwrap().lost.clone() else {
+      return Ok(false);
+    };
+    log::warn!("[device]: lost ({}), recreating it", reason);
+
+    let (device, queue) = request_device(&self.gpu.adapter, &self.label, &self.limits).await
+      .map_err(|e| DeviceError::Recreate(e.to_string()))?;
+    self.watch = watch(&device);
+    self.gpu.device = device;
+    self.gpu.queue = queue;
+
+    self.generation += 1;
+    log::info!("[device]: recreated, generation {}", self.generation);
+    Ok(true)
+  }
+}
+
+// Installs the error and device-lost callbacks; each device reports into its own `Watch`.
+fn watch(device: &wgpu::Device) -> Arc<Mutex<Watch>> {
+  let watch = Arc::new(Mutex::new(Watch::default()));
+
+  let errors = watch.clone();
+  device.on_uncaptured_error(Box::new(move |e| {
+    log::error!("[device]: uncaptured {}", e);
+    let mut watch = errors.lock().unwrap();
+    if watch.errors.len() == MAX_ERRORS {
+      watch.errors.remove(0);
+    }
+    watch.errors.push(DeviceError::Uncaptured(e.to_string()));
+  }));
+
+  let lost = watch.clone();
+  device.set_device_lost_callback(move |reason, message| {
+    // dropping the old device after a rebuild reports too
+    if matches!(reason, wgpu::DeviceLostReason::Dropped | wgpu::DeviceLostReason::ReplacedCallback) {
+      return;
+    }
+    log::error!("[device]: lost {:?} {}", reason, message);
+    lost.lock().unwrap().lost = Some(format!("{reason:?} {message}"));
+  });
+
+  watch
+}
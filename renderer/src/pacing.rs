@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::Instant;
+
+// `fifo`, `fifo-relaxed`, `mailbox`, `immediate`, `auto-vsync` or `auto-no-vsync`.
+pub fn parse_present_mode(s: &str) -> Result<wgpu::PresentMode, String> {
+  match s.to_ascii_lowercase().replace('_', "-").as_str() {
+    "fifo" => Ok(wgpu::PresentMode::Fifo),
+    "fifo-relaxed" => Ok(wgpu::PresentMode::FifoRelaxed),
+    "mailbox" => Ok(wgpu::PresentMode::Mailbox),
+    "immediate" => Ok(wgpu::PresentMode::Immediate),
+    "auto-vsync" => Ok(wgpu::PresentMode::AutoVsync),
+    "auto-no-vsync" => Ok(wgpu::PresentMode::AutoNoVsync),
+    _ => Err(format!(
+      "unknown present mode `{s}`, expected fifo, fifo-relaxed, mailbox, immediate, auto-vsync or auto-no-vsync"
+    )),
+  }
+}
+
+// `requested` if the surface supports it, otherwise the closest mode that it does: the modes
+// without vsync fall back to each other before `Fifo`, which every surface supports. The auto
+// modes are always valid, wgpu picks from the supported ones itself.
+pub fn select_present_mode(requested: wgpu::PresentMode, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+  use wgpu::PresentMode::*;
+
+  let fallbacks: &[wgpu::PresentMode] = match requested {
+    AutoVsync | AutoNoVsync => return requested,
+    Immediate => &[Immediate, Mailbox],
+    Mailbox => &[Mailbox, Immediate],
+    FifoRelaxed => &[FifoRelaxed],
+    Fifo => &[],
+  };
+  let selected = fallbacks.iter().copied().find(|mode| supported.contains(mode)).unwrap_or(Fifo);
+  if selected != requested {
+    log::warn!("[present]: {:?} isn't supported, using {:?}", requested, selected);
+  }
+  selected
+}
+
+// The time between frames at `target_fps`. `None` for zero, negative and non-finite rates,
+// and for rates so low the interval doesn't fit a `Duration`.
+pub fn frame_interval(target_fps: f64) -> Option<Duration> {
+  if !target_fps.is_finite() || target_fps <= 0.0 {
+    return None;
+  }
+  Duration::try_from_secs_f64(1.0 / target_fps).ok()
+}
+
+// Paces a loop that waits for events to a target frame rate: after each frame it says when
+// the next one is due, without drifting when frames are a little late.
+#[derive(Clone, Debug, Default)]
+pub struct FrameLimiter {
+  interval: Option<Duration>,
+  next: Option<Instant>,
+}
+
+impl FrameLimiter {
+  // `None` and rates without a `frame_interval` don't limit.
+  pub fn new(target_fps: Option<f64>) -> Self {
+    Self { interval: target_fps.and_then(frame_interval), next: None }
+  }
+
+  pub fn interval(&self) -> Option<Duration> {
+    self.interval
+  }
+
+  // Call when a frame was drawn at `now`. Returns when to draw the next one, or `None` for
+  // right away. Frames drawn early, e.g. for a resize, keep the schedule, a frame more than
+  // an interval late starts it over.
+  pub fn frame_done(&mut self, now: Instant) -> Option<Instant> {
+    let interval = self.interval?;
+    let next = match self.next {
+      Some(due) if now < due => due,
+      Some(due) if now < due + interval => due + interval,
+      _ => now + interval,
+    };
+    self.next = Some(next);
+    Some(next)
+  }
+}
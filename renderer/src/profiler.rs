@@ -0,0 +1,279 @@
+use std::{
+  cell::RefCell, collections::VecDeque, fmt, sync::{
+    atomic::{AtomicU8, Ordering}, Arc
+  }, time::Duration
+};
+
+use crate::{Gpu, Instant};
+
+// CPU frame times over the last frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+  pub frames: u64,
+  // between the last two frames
+  pub frame_time: Duration,
+  // over the rolling window
+  pub average: Duration,
+  pub fps: f64,
+}
+
+// Measures the time between frames and keeps a rolling average over the last `window` frames.
+#[derive(Clone, Debug)]
+pub struct FrameTimer {
+  window: usize,
+  samples: VecDeque<Duration>,
+  total: Duration,
+  last: Option<Instant>,
+  frames: u64,
+}
+
+impl FrameTimer {
+  pub fn new(window: usize) -> Self {
+    let window = window.max(1);
+    Self { window, samples: VecDeque::with_capacity(window), total: Duration::ZERO, last: None, frames: 0 }
+  }
+
+  // Call once per frame, at the same point of it.
+  pub fn tick(&mut self, now: Instant) {
+    self.frames += 1;
+    if let Some(last) = self.last.replace(now) {
+      let frame_time = now.saturating_duration_since(last);
+      if self.samples.len() == self.window {
+        self.total -= self.samples.pop_front().unwrap();
+      }
+      self.samples.push_back(frame_time);
+      self.total += frame_time;
+    }
+  }
+
+  pub fn stats(&self) -> FrameStats {
+    let average = match self.samples.len() {
+      0 => Duration::ZERO,
+      n => self.total / n as u32,
+    };
+    FrameStats {
+      frames: self.frames,
+      frame_time: self.samples.back().copied().unwrap_or_default(),
+      average,
+      fps: if average.is_zero() { 0.0 } else { 1.0 / average.as_secs_f64() },
+    }
+  }
+}
+
+impl Default for FrameTimer {
+  fn default() -> Self {
+    Self::new(60)
+  }
+}
+
+// How long one labeled pass took on the GPU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+  pub label: String,
+  pub duration: Duration,
+}
+
+// Turns the begin and end timestamps of each pass, in ticks of `period` nanoseconds, into
+// durations. Passes whose end isn't after their begin, e.g. when the counter wrapped, are 0.
+pub fn pass_timings(labels: &[String], timestamps: &[u64], period: f32) -> Vec<PassTiming> {
+  labels.iter().zip(timestamps.chunks_exact(2)).map(|(label, pair)| {
+    let ticks = pair[1].saturating_sub(pair[0]);
+    PassTiming { label: label.clone(), duration: Duration::from_nanos((ticks as f64 * period as f64) as u64) }
+  }).collect()
+}
+
+// Mapping the readback buffer is asynchronous, frames recorded meanwhile aren't timed.
+enum Readback {
+  Idle,
+  Mapping { labels: Vec<String>, status: Arc<AtomicU8> },
+}
+
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+// Times render passes with timestamp queries. Passes ask for their `timestamp_writes`
+// through the `Frame`; the results arrive a frame or more later.
+pub struct GpuTimer {
+  query_set: wgpu::QuerySet,
+  resolve: wgpu::Buffer,
+  readback: wgpu::Buffer,
+  period: f32,
+  // the passes recorded this frame, `None` while the last frame is still being read
+  labels: RefCell<Option<Vec<String>>>,
+  state: Readback,
+  timings: Vec<PassTiming>,
+}
+
+impl GpuTimer {
+  pub const MAX_PASSES: u32 = 16;
+
+  // `None` if the device wasn't opened with `TIMESTAMP_QUERY`.
+  pub fn new(gpu: &Gpu) -> Option<Self> {
+    if !gpu.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+      return None;
+    }
+    let count = Self::MAX_PASSES * 2;
+    let size = count as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress;
+    let query_set = gpu.device.create_query_set(&wgpu::QuerySetDescriptor {
+      label: Some("Timestamp Query Set"),
+      ty: wgpu::QueryType::Timestamp,
+      count,
+    });
+    let resolve = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Timestamp Resolve Buffer"),
+      size,
+      usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+    let readback = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Timestamp Readback Buffer"),
+      size,
+      usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    Some(Self {
+      query_set,
+      resolve,
+      readback,
+      period: gpu.queue.get_timestamp_period(),
+      labels: RefCell::new(None),
+      state: Readback::Idle,
+      timings: Vec::new(),
+    })
+  }
+
+  // The latest timings, in the order the passes were recorded.
+  pub fn timings(&self) -> &[PassTiming] {
+    &self.timings
+  }
+
+  // Picks up the timings of an earlier frame if they are ready and starts recording.
+  pub fn begin_frame(&mut self, device: &wgpu::Device) {
+    if let Readback::Mapping { status, .. } = &self.state {
+      device.poll(wgpu::Maintain::Poll);
+      let status = status.load(Ordering::Acquire);
+      if status == PENDING {
+        return;
+      }
+      let Readback::Mapping { labels, .. } = std::mem::replace(&mut self.state, Readback::Idle) else {
+        unreachable!()
+      };
+      if status == MAPPED {
+        {
+          let data = self.readback.slice(..).get_mapped_range();
+          let timestamps: Vec<u64> = data.chunks_exact(8).map(|b| u64::from_le_bytes(b.try_into().unwrap())).collect();
+          self.timings = pass_timings(&labels, &timestamps, self.period);
+        }
+        self.readback.unmap();
+      }
+    }
+    *self.labels.borrow_mut() = Some(Vec::new());
+  }
+
+  // For the descriptor of a pass labeled `label`; `None` once `MAX_PASSES` are timed or
+  // while the frame isn't being recorded.
+  pub fn render_pass_writes(&self, label: &str) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+    let mut labels = self.labels.borrow_mut();
+    let labels = labels.as_mut()?;
+    let index = labels.len() as u32;
+    if index == Self::MAX_PASSES {
+      return None;
+    }
+    labels.push(label.to_string());
+    Some(wgpu::RenderPassTimestampWrites {
+      query_set: &self.query_set,
+      beginning_of_pass_write_index: Some(index * 2),
+      end_of_pass_write_index: Some(index * 2 + 1),
+    })
+  }
+
+  // Resolves this frame's queries into the readback buffer, the last command of the frame.
+  pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+    let labels = self.labels.borrow();
+    let Some(count) = labels.as_ref().map(|labels| labels.len() as u32 * 2).filter(|count| *count > 0) else {
+      return;
+    };
+    encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve, 0);
+    let size = count as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress;
+    encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, size);
+  }
+
+  // Call after submitting, starts reading the timestamps back.
+  pub fn end_frame(&mut self) {
+    let Some(labels) = self.labels.borrow_mut().take().filter(|labels| !labels.is_empty()) else {
+      return;
+    };
+    let status = Arc::new(AtomicU8::new(PENDING));
+    let done = status.clone();
+    self.readback.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+      done.store(if result.is_ok() { MAPPED } else { FAILED }, Ordering::Release);
+    });
+    self.state = Readback::Mapping { labels, status };
+  }
+}
+
+// CPU frame times and, on devices with timestamp queries, GPU pass times.
+pub struct Profiler {
+  cpu: FrameTimer,
+  gpu: Option<GpuTimer>,
+}
+
+impl Profiler {
+  pub fn new(gpu: &Gpu) -> Self {
+    let timer = GpuTimer::new(gpu);
+    if timer.is_none() {
+      log::info!("[profiler]: no timestamp queries on this device, timing the CPU only");
+    }
+    Self { cpu: FrameTimer::default(), gpu: timer }
+  }
+
+  pub fn stats(&self) -> FrameStats {
+    self.cpu.stats()
+  }
+
+  // Empty without timestamp queries.
+  pub fn gpu_timings(&self) -> &[PassTiming] {
+    self.gpu.as_ref().map(GpuTimer::timings).unwrap_or_default()
+  }
+
+  pub fn gpu_timer(&self) -> Option<&GpuTimer> {
+    self.gpu.as_ref()
+  }
+
+  pub(crate) fn begin_frame(&mut self, device: &wgpu::Device, now: Instant) {
+    self.cpu.tick(now);
+    if let Some(gpu) = &mut self.gpu {
+      gpu.begin_frame(device);
+    }
+  }
+
+  pub(crate) fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+    if let Some(gpu) = &self.gpu {
+      gpu.resolve(encoder);
+    }
+  }
+
+  pub(crate) fn end_frame(&mut self) {
+    if let Some(gpu) = &mut self.gpu {
+      gpu.end_frame();
+    }
+  }
+}
+
+// e.g. `60.0 fps, 16.67 ms (last 16.50 ms), gpu: First Render Pass 1.20 ms`
+impl fmt::Display for Profiler {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let stats = self.stats();
+    write!(f, "{:.1} fps, {:.2} ms (last {:.2} ms)", stats.fps, ms(stats.average), ms(stats.frame_time))?;
+    for (i, timing) in self.gpu_timings().iter().enumerate() {
+      write!(f, "{} {} {:.2} ms", if i == 0 { ", gpu:" } else { "," }, timing.label, ms(timing.duration))?;
+    }
+    Ok(())
+  }
+}
+
+fn ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
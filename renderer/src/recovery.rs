@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use crate::Instant;
+
+// What happened while acquiring a frame, for the embedding app to observe through
+// `Scene::surface_event`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SurfaceEvent {
+  // the surface was lost or outdated and has been configured again
+  Reconfigured(wgpu::SurfaceError),
+  // acquiring timed out, the frame was dropped
+  FrameSkipped(wgpu::SurfaceError),
+  // reconfiguring didn't help, frames are skipped for `delay` before the next attempt
+  Backoff { attempt: u32, delay: Duration },
+  // a frame was acquired again after `attempts` failed ones
+  Recovered { attempts: u32 },
+  // out of memory, or still failing after `RecoveryPolicy::max_attempts`
+  Fatal(wgpu::SurfaceError),
+}
+
+// How hard `SurfaceRecovery` tries before giving up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecoveryPolicy {
+  // failed attempts in a row, each after reconfiguring, before the error is fatal
+  pub max_attempts: u32,
+  // the wait after the first failed attempt, doubled after every further one
+  pub initial_backoff: Duration,
+  pub max_backoff: Duration,
+}
+
+impl Default for RecoveryPolicy {
+  fn default() -> Self {
+    Self {
+      max_attempts: 8,
+      initial_backoff: Duration::from_millis(16),
+      max_backoff: Duration::from_secs(1),
+    }
+  }
+}
+
+impl RecoveryPolicy {
+  // How long to wait after the `attempt`th failure in a row, counting from 1.
+  pub fn backoff(&self, attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+    self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+  }
+}
+
+// What `SurfaceRecovery` needs from a surface, so it can be driven by a mock in tests.
+pub trait FrameSurface {
+  type Frame;
+
+  fn acquire(&mut self) -> Result<Self::Frame, wgpu::SurfaceError>;
+
+  // Configures the surface again with its current configuration.
+  fn reconfigure(&mut self);
+}
+
+#[derive(Debug)]
+pub enum Acquired<F> {
+  Frame(F),
+  // nothing to draw into this time, try again with the next frame
+  Skip,
+  // the surface can't be recovered, the app should exit
+  Fatal(wgpu::SurfaceError),
+}
+
+// Acquires frames, reconfiguring the surface when it is lost or outdated, dropping frames
+// that time out and backing off when reconfiguring doesn't help.
+#[derive(Debug, Default)]
+pub struct SurfaceRecovery {
+  policy: RecoveryPolicy,
+  failures: u32,
+  retry_at: Option<Instant>,
+}
+
+impl SurfaceRecovery {
+  pub fn new(policy: RecoveryPolicy) -> Self {
+    Self { policy, failures: 0, retry_at: None }
+  }
+
+  pub fn policy(&self) -> &RecoveryPolicy {
+    &self.policy
+  }
+
+  // Failed attempts since the last acquired frame.
+  pub fn failures(&self) -> u32 {
+    self.failures
+  }
+
+  // When the next attempt is due, while backing off.
+  pub fn retry_at(&self) -> Option<Instant> {
+    self.retry_at
+  }
+
+  pub fn acquire<S: FrameSurface>(
+    &mut self,
+    surface: &mut S,
+    now: Instant,
+    on_event: &mut dyn FnMut(SurfaceEvent),
+  ) -> Acquired<S::Frame> {
+    if self.retry_at.is_some_and(|at| now < at) {
+      return Acquired::Skip;
+    }
+
+    let result = match surface.acquire() {
+      Err(error @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+        surface.reconfigure();
+        on_event(SurfaceEvent::Reconfigured(error));
+        surface.acquire()
+      }
+      result => result,
+    };
+
+    match result {
+      Ok(frame) => {
+        if self.failures > 0 {
+          on_event(SurfaceEvent::Recovered { attempts: self.failures });
+        }
+        self.failures = 0;
+        self.retry_at = None;
+        Acquired::Frame(frame)
+      }
+      Err(error @ wgpu::SurfaceError::Timeout) => {
+        on_event(SurfaceEvent::FrameSkipped(error));
+        Acquired::Skip
+      }
+      Err(error @ wgpu::SurfaceError::OutOfMemory) => {
+        on_event(SurfaceEvent::Fatal(error.clone()));
+        Acquired::Fatal(error)
+      }
+      Err(error) => {
+        self.failures += 1;
+        if self.failures >= self.policy.max_attempts {
+          self.retry_at = None;
+          on_event(SurfaceEvent::Fatal(error.clone()));
+          return Acquired::Fatal(error);
+        }
+        let delay = self.policy.backoff(self.failures);
+        self.retry_at = Some(now + delay);
+        on_event(SurfaceEvent::Backoff { attempt: self.failures, delay });
+        Acquired::Skip
+      }
+    }
+  }
+}
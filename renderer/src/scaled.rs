@@ -0,0 +1,120 @@
+use crate::GpuTimer;
+
+// The offscreen target scenes draw into when the render scale isn't 1, and the pass that
+// scales it to the output with a linear filter.
+pub(crate) struct ScaledTarget {
+  width: u32,
+  height: u32,
+  view: wgpu::TextureView,
+  sampler: wgpu::Sampler,
+  pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+}
+
+impl ScaledTarget {
+  pub(crate) fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+    let view = Self::create_view(device, format, width, height);
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("Scaled Sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("scale.wgsl"),
+      source: wgpu::ShaderSource::Wgsl(include_str!("scale.wgsl").into()),
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+      label: Some("Scale Pipeline"),
+      layout: None,
+      vertex: wgpu::VertexState {
+        module: &shader,
+        entry_point: "vs_main",
+        buffers: &[],
+      },
+      fragment: Some(wgpu::FragmentState {
+        module: &shader,
+        entry_point: "fs_main",
+        targets: &[Some(format.into())],
+      }),
+      primitive: wgpu::PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: wgpu::MultisampleState::default(),
+      multiview: None,
+    });
+    let bind_group = Self::bind_group(device, &pipeline, &view, &sampler);
+
+    Self { width, height, view, sampler, pipeline, bind_group }
+  }
+
+  fn create_view(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> wgpu::TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Scaled Target"),
+      size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+  }
+
+  fn bind_group(
+    device: &wgpu::Device,
+    pipeline: &wgpu::RenderPipeline,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+  ) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Scale Bind Group"),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(sampler),
+        },
+      ],
+    })
+  }
+
+  // Where scenes draw, in the format the target was created with.
+  pub(crate) fn view(&self) -> &wgpu::TextureView {
+    &self.view
+  }
+
+  pub(crate) fn resize(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) {
+    if (self.width, self.height) == (width, height) {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+    self.view = Self::create_view(device, format, width, height);
+    self.bind_group = Self::bind_group(device, &self.pipeline, &self.view, &self.sampler);
+  }
+
+  // Draws the target over all of `output`.
+  pub(crate) fn encode(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView, timer: Option<&GpuTimer>) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Scale Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: output,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      timestamp_writes: timer.and_then(|timer| timer.render_pass_writes("Scale Pass")),
+      ..Default::default()
+    });
+    pass.set_pipeline(&self.pipeline);
+    pass.set_bind_group(0, &self.bind_group, &[]);
+    pass.draw(0..3, 0..1);
+  }
+}
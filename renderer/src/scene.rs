@@ -0,0 +1,128 @@
+use winit::event::WindowEvent;
+
+use crate::{Gpu, GpuTimer, SurfaceEvent};
+
+// What a scene draws into this frame.
+#[derive(Clone, Copy)]
+pub struct Frame<'a> {
+  pub gpu: &'a Gpu,
+  pub view: &'a wgpu::TextureView,
+  pub format: wgpu::TextureFormat,
+  pub width: u32,
+  pub height: u32,
+  // set while the renderer profiles the GPU
+  pub timer: Option<&'a GpuTimer>,
+}
+
+impl<'a> Frame<'a> {
+  // For `RenderPassDescriptor::timestamp_writes`, times the pass as `label` when profiling.
+  pub fn timestamp_writes(&self, label: &str) -> Option<wgpu::RenderPassTimestampWrites<'a>> {
+    self.timer.and_then(|timer| timer.render_pass_writes(label))
+  }
+}
+
+// One step of a frame, e.g. a pass drawing into `Frame::view`.
+pub trait RenderNode {
+  // Called when the frame size changes, to reallocate size dependent targets.
+  fn resize(&mut self, _gpu: &Gpu, _width: u32, _height: u32) {}
+
+  fn encode(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder);
+}
+
+// What the `Renderer` draws every frame. The renderer owns the window, the surface and the
+// event loop; a scene only sees the events it didn't handle and the frames to draw.
+pub trait Scene {
+  // Returns whether the scene used the event.
+  fn input(&mut self, _event: &WindowEvent) -> bool {
+    false
+  }
+
+  // Per-frame state, run before `render`.
+  fn update(&mut self, _gpu: &Gpu) {}
+
+  fn resize(&mut self, _gpu: &Gpu, _width: u32, _height: u32) {}
+
+  // Told about every lost, outdated or skipped frame; the renderer has already handled it.
+  fn surface_event(&mut self, _event: &SurfaceEvent) {}
+
+  // Creates the scene's GPU objects again after the device was lost and recreated, keeping
+  // its other state, e.g. the camera. Scenes that return false, as by default, are thrown away
+  // and built again.
+  fn recreate(&mut self, _gpu: &Gpu) -> bool {
+    false
+  }
+
+  fn render(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder);
+}
+
+// A scene that is nothing but nodes, encoded in the order they were added.
+#[derive(Default)]
+pub struct NodeScene {
+  nodes: Vec<Box<dyn RenderNode>>,
+}
+
+impl NodeScene {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with(mut self, node: impl RenderNode + 'static) -> Self {
+    self.nodes.push(Box::new(node));
+    self
+  }
+
+  pub fn push(&mut self, node: impl RenderNode + 'static) {
+    self.nodes.push(Box::new(node));
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+}
+
+impl Scene for NodeScene {
+  fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+    for node in &mut self.nodes {
+      node.resize(gpu, width, height);
+    }
+  }
+
+  fn render(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    for node in &self.nodes {
+      node.encode(frame, encoder);
+    }
+  }
+}
+
+// Clears the frame to a color.
+pub struct ClearNode {
+  pub color: wgpu::Color,
+}
+
+impl ClearNode {
+  pub fn new(color: wgpu::Color) -> Self {
+    Self { color }
+  }
+}
+
+impl RenderNode for ClearNode {
+  fn encode(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Clear Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: frame.view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(self.color),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      timestamp_writes: frame.timestamp_writes("Clear Pass"),
+      ..Default::default()
+    });
+  }
+}
@@ -0,0 +1,103 @@
+use winit::dpi::{LogicalSize, PhysicalSize};
+
+// The size of a window surface. The surface is always configured with the physical size,
+// scenes draw at `render_size`, which the render scale makes larger to supersample or
+// smaller to undersample, as far as textures of `max_dimension` allow.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceSize {
+  physical: PhysicalSize<u32>,
+  scale_factor: f64,
+  render_scale: f32,
+  max_dimension: u32,
+}
+
+impl SurfaceSize {
+  pub const MIN_RENDER_SCALE: f32 = 0.125;
+  pub const MAX_RENDER_SCALE: f32 = 4.0;
+
+  // A zero width or height, e.g. of a minimized window, is raised to 1.
+  pub fn new(physical: PhysicalSize<u32>, scale_factor: f64) -> Self {
+    let physical = PhysicalSize::new(physical.width.max(1), physical.height.max(1));
+    Self { physical, scale_factor, render_scale: 1.0, max_dimension: u32::MAX }
+  }
+
+  pub fn with_render_scale(mut self, render_scale: f32) -> Self {
+    self.set_render_scale(render_scale);
+    self
+  }
+
+  // The largest width or height the render size may have, the device's
+  // `max_texture_dimension_2d`.
+  pub fn with_max_dimension(mut self, max_dimension: u32) -> Self {
+    self.max_dimension = max_dimension.max(1);
+    self
+  }
+
+  pub fn physical(&self) -> PhysicalSize<u32> {
+    self.physical
+  }
+
+  // The size in points, what layouts and input coordinates are in.
+  pub fn logical(&self) -> LogicalSize<f64> {
+    self.physical.to_logical(self.scale_factor)
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.scale_factor
+  }
+
+  pub fn render_scale(&self) -> f32 {
+    self.render_scale
+  }
+
+  pub fn is_scaled(&self) -> bool {
+    self.render_scale != 1.0
+  }
+
+  // The render scale lowered until the render size fits `max_dimension`, but not below 1,
+  // the surface itself is limited by the device.
+  pub fn effective_render_scale(&self) -> f32 {
+    let longest = self.physical.width.max(self.physical.height) as f32;
+    self.render_scale.min((self.max_dimension as f32 / longest).max(1.0))
+  }
+
+  pub fn is_render_scale_clamped(&self) -> bool {
+    self.effective_render_scale() < self.render_scale
+  }
+
+  // The size of what scenes draw, at least 1x1.
+  pub fn render_size(&self) -> (u32, u32) {
+    let render_scale = self.effective_render_scale();
+    let scale = |length: u32| ((length as f32 * render_scale).round() as u32).clamp(1, self.max_dimension.max(length));
+    (scale(self.physical.width), scale(self.physical.height))
+  }
+
+  // Returns whether anything changed; zero sizes of minimized windows are ignored.
+  pub fn resize(&mut self, physical: PhysicalSize<u32>) -> bool {
+    if physical.width == 0 || physical.height == 0 || physical == self.physical {
+      return false;
+    }
+    self.physical = physical;
+    true
+  }
+
+  // The physical size follows with the next resize.
+  pub fn set_scale_factor(&mut self, scale_factor: f64) -> bool {
+    if scale_factor <= 0.0 || scale_factor == self.scale_factor {
+      return false;
+    }
+    self.scale_factor = scale_factor;
+    true
+  }
+
+  // Clamped to `MIN_RENDER_SCALE..=MAX_RENDER_SCALE`.
+  pub fn set_render_scale(&mut self, render_scale: f32) -> bool {
+    let render_scale = if render_scale.is_nan() { 1.0 } else { render_scale };
+    let render_scale = render_scale.clamp(Self::MIN_RENDER_SCALE, Self::MAX_RENDER_SCALE);
+    if render_scale == self.render_scale {
+      return false;
+    }
+    self.render_scale = render_scale;
+    true
+  }
+}
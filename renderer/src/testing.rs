@@ -0,0 +1,100 @@
+// Golden-image checks for the examples' tests: a rendered frame is compared with a reference
+// PNG kept in the crate under test.
+use std::{
+  fs::{self, File},
+  io::BufWriter,
+  path::{Path, PathBuf},
+};
+
+// How far a rendered frame may drift from its reference image before the test fails.
+pub struct Tolerance {
+  // largest allowed absolute difference of a single channel
+  pub per_channel: u8,
+  // pixels allowed to exceed `per_channel`, to absorb rasterization differences along edges
+  pub max_failing_pixels: usize,
+}
+
+// Where a crate keeps its reference images and where failing frames are written.
+pub struct Golden {
+  references: PathBuf,
+  output: PathBuf,
+}
+
+impl Golden {
+  // References are read from `<manifest_dir>/tests/reference`, failures go to `<tmp_dir>/golden`.
+  // Tests pass `env!("CARGO_MANIFEST_DIR")` and `env!("CARGO_TARGET_TMPDIR")`.
+  pub fn new(manifest_dir: impl AsRef<Path>, tmp_dir: impl AsRef<Path>) -> Self {
+    Self {
+      references: manifest_dir.as_ref().join("tests").join("reference"),
+      output: tmp_dir.as_ref().join("golden"),
+    }
+  }
+
+  // Compares an RGBA8 frame with the reference `<name>.png`.
+  // Run with `UPDATE_GOLDEN=1` to (re)write the reference from the current output.
+  // On failure the actual frame and a diff image are written to the output directory.
+  pub fn assert_matches(&self, name: &str, width: u32, height: u32, pixels: &[u8], tolerance: Tolerance) {
+    let reference = self.references.join(format!("{name}.png"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+      write_png(&reference, width, height, pixels);
+      return;
+    }
+
+    let (ref_width, ref_height, expected) = read_png(&reference);
+    assert_eq!(
+      (ref_width, ref_height), (width, height),
+      "[golden]: {name} is {width}x{height} but the reference is {ref_width}x{ref_height}"
+    );
+
+    let mut failing_pixels = 0;
+    let mut max_difference = 0;
+    // mismatches in red over a dimmed copy of the actual frame
+    let mut diff = Vec::with_capacity(pixels.len());
+    for (actual, expected) in pixels.chunks(4).zip(expected.chunks(4)) {
+      let difference = actual.iter().zip(expected).map(|(a, e)| a.abs_diff(*e)).max().unwrap();
+      max_difference = max_difference.max(difference);
+      if difference > tolerance.per_channel {
+        failing_pixels += 1;
+        diff.extend_from_slice(&[255, 0, 0, 255]);
+      } else {
+        let luma = ((actual[0] as u32 + actual[1] as u32 + actual[2] as u32) / 3 / 4) as u8;
+        diff.extend_from_slice(&[luma, luma, luma, 255]);
+      }
+    }
+
+    if failing_pixels > tolerance.max_failing_pixels {
+      let actual_path = self.output.join(format!("{name}.actual.png"));
+      let diff_path = self.output.join(format!("{name}.diff.png"));
+      write_png(&actual_path, width, height, pixels);
+      write_png(&diff_path, width, height, &diff);
+      panic!(
+        "[golden]: {name} differs from {} in {failing_pixels} pixels (max channel difference {max_difference}), \
+         actual: {}, diff: {}",
+        reference.display(), actual_path.display(), diff_path.display()
+      );
+    }
+  }
+}
+
+pub fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+  let file = File::open(path).unwrap_or_else(|e| panic!("couldn't open {}: {e}", path.display()));
+  let mut reader = png::Decoder::new(file).read_info().unwrap();
+  let mut pixels = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut pixels).unwrap();
+  assert_eq!(info.color_type, png::ColorType::Rgba, "{} is not RGBA", path.display());
+  assert_eq!(info.bit_depth, png::BitDepth::Eight, "{} is not 8 bit", path.display());
+  pixels.truncate(info.buffer_size());
+  (info.width, info.height, pixels)
+}
+
+pub fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).unwrap();
+  }
+  let file = File::create(path).unwrap_or_else(|e| panic!("couldn't create {}: {e}", path.display()));
+  let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
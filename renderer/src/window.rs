@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use winit::{dpi::PhysicalSize, window::Window};
+
+use crate::{
+  scaled::ScaledTarget, select_present_mode, Acquired, ColorPolicy, ColorSpace, DeviceError, DeviceLifecycle, Frame,
+  FrameSurface, Gpu, GpuError, Instant, Profiler, RecoveryPolicy, Scene, SurfaceEvent, SurfaceRecovery, SurfaceSize,
+  SurfaceSupport,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::capture::{self, CaptureError, CapturedFrame};
+
+// Presents scenes to a window: owns the surface and its configuration, so resizing and the
+// sRGB decisions are made here once instead of in every example.
+pub struct Renderer {
+  device: DeviceLifecycle,
+  surface: wgpu::Surface<'static>,
+  config: wgpu::SurfaceConfiguration,
+  present_modes: Vec<wgpu::PresentMode>,
+  size: SurfaceSize,
+  // only while the render scale isn't 1
+  scaled: Option<ScaledTarget>,
+  policy: ColorPolicy,
+  recovery: SurfaceRecovery,
+  profiler: Profiler,
+  // copy the next frame out of the surface and save it
+  #[cfg(not(target_arch = "wasm32"))]
+  capture_next: bool,
+  window: Arc<Window>,
+}
+
+// The window surface as `SurfaceRecovery` sees it.
+struct WindowSurface<'a> {
+  surface: &'a wgpu::Surface<'static>,
+  device: &'a wgpu::Device,
+  config: &'a wgpu::SurfaceConfiguration,
+}
+
+impl FrameSurface for WindowSurface<'_> {
+  type Frame = wgpu::SurfaceTexture;
+
+  fn acquire(&mut self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+    self.surface.get_current_texture()
+  }
+
+  fn reconfigure(&mut self) {
+    self.surface.configure(self.device, self.config);
+  }
+}
+
+fn log_event(event: &SurfaceEvent) {
+  match event {
+    SurfaceEvent::Recovered { attempts } => log::info!("[surface]: recovered after {} attempts", attempts),
+    SurfaceEvent::Fatal(e) => log::error!("[surface]: {:?}, giving up", e),
+    event => log::warn!("[surface]: {:?}", event),
+  }
+}
+
+impl Renderer {
+  pub async fn new(window: Arc<Window>, color_space: ColorSpace, recovery: RecoveryPolicy) -> Result<Self, GpuError> {
+    // The instance is a handler to the GPU
+    // Backend::all => Vulkan + Metal + DX12 + Browser WebGPU
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+      backends: wgpu::Backends::all(),
+      ..Default::default()
+    });
+    // the surface keeps the window alive through the `Arc`
+    let surface = instance.create_surface(window.clone()).unwrap();
+    let gpu = Gpu::new(&instance, Some(&surface)).await?;
+
+    let surface_caps = surface.get_capabilities(&gpu.adapter);
+    let policy = ColorPolicy::new(&surface_caps.formats, color_space, SurfaceSupport::new(&gpu.adapter));
+    log::info!("[renderer]: surface {:?}, rendering to {:?}", policy.surface_format, policy.render_format);
+
+    let size = SurfaceSize::new(window.inner_size(), window.scale_factor())
+      .with_max_dimension(gpu.device.limits().max_texture_dimension_2d);
+    // captures copy frames straight out of the surface where it allows that
+    #[cfg(not(target_arch = "wasm32"))]
+    let usage = match capture::can_copy_surface(&surface_caps, policy.surface_format) {
+      true => wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+      false => wgpu::TextureUsages::RENDER_ATTACHMENT,
+    };
+    #[cfg(target_arch = "wasm32")]
+    let usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+    let config = wgpu::SurfaceConfiguration {
+      usage,
+      format: policy.surface_format,
+      width: size.physical().width,
+      height: size.physical().height,
+      present_mode: wgpu::PresentMode::Fifo,
+      alpha_mode: surface_caps.alpha_modes[0],
+      view_formats: policy.view_formats.clone(),
+      desired_maximum_frame_latency: 2,
+    };
+    surface.configure(&gpu.device, &config);
+
+    let profiler = Profiler::new(&gpu);
+    let device = DeviceLifecycle::new(gpu, "Device", Gpu::default_limits());
+    Ok(Self {
+      device,
+      surface,
+      config,
+      present_modes: surface_caps.present_modes,
+      size,
+      scaled: None,
+      policy,
+      recovery: SurfaceRecovery::new(recovery),
+      profiler,
+      #[cfg(not(target_arch = "wasm32"))]
+      capture_next: false,
+      window,
+    })
+  }
+
+  pub fn gpu(&self) -> &Gpu {
+    self.device.gpu()
+  }
+
+  pub fn device(&self) -> &DeviceLifecycle {
+    &self.device
+  }
+
+  // Recreates a lost device and reconfigures the surface for it. Everything the scene created
+  // belongs to the old device, so follow `Ok(true)` with `Scene::recreate`.
+  pub async fn recover_device(&mut self) -> Result<bool, DeviceError> {
+    if !self.device.recover().await? {
+      return Ok(false);
+    }
+    self.surface.configure(&self.gpu().device, &self.config);
+    let (width, height) = self.size();
+    self.scaled = self.size.is_scaled().then(|| ScaledTarget::new(&self.gpu().device, self.format(), width, height));
+    self.profiler = Profiler::new(self.device.gpu());
+    Ok(true)
+  }
+
+  // Frame times, and pass times on devices with timestamp queries.
+  pub fn profiler(&self) -> &Profiler {
+    &self.profiler
+  }
+
+  pub fn window(&self) -> &Window {
+    &self.window
+  }
+
+  pub fn policy(&self) -> &ColorPolicy {
+    &self.policy
+  }
+
+  // The format of the views scenes render into.
+  pub fn format(&self) -> wgpu::TextureFormat {
+    self.policy.render_format
+  }
+
+  pub fn present_mode(&self) -> wgpu::PresentMode {
+    self.config.present_mode
+  }
+
+  // What the surface supports besides the auto modes.
+  pub fn supported_present_modes(&self) -> &[wgpu::PresentMode] {
+    &self.present_modes
+  }
+
+  // Switches to `requested`, or to the closest supported mode, see `select_present_mode`.
+  // Returns the mode in use.
+  pub fn set_present_mode(&mut self, requested: wgpu::PresentMode) -> wgpu::PresentMode {
+    let present_mode = select_present_mode(requested, &self.present_modes);
+    if present_mode != self.config.present_mode {
+      log::info!("[present]: {:?}", present_mode);
+      self.config.present_mode = present_mode;
+      self.surface.configure(&self.device.gpu().device, &self.config);
+    }
+    present_mode
+  }
+
+  pub fn frame_latency(&self) -> u32 {
+    self.config.desired_maximum_frame_latency
+  }
+
+  // How many frames may be queued ahead of the display, at least 1. Lower means less input
+  // lag, higher smoother frame times; the backend may not honor it exactly.
+  pub fn set_frame_latency(&mut self, frames: u32) {
+    let frames = frames.max(1);
+    if frames != self.config.desired_maximum_frame_latency {
+      self.config.desired_maximum_frame_latency = frames;
+      self.surface.configure(&self.device.gpu().device, &self.config);
+    }
+  }
+
+  // The size scenes draw at, the physical size of the surface times the render scale.
+  pub fn size(&self) -> (u32, u32) {
+    self.size.render_size()
+  }
+
+  pub fn surface_size(&self) -> &SurfaceSize {
+    &self.size
+  }
+
+  // Reconfigures the surface for a new physical size. Returns whether the size scenes draw at
+  // changed; nothing does for the zero size of a minimized window.
+  pub fn resize(&mut self, physical: PhysicalSize<u32>) -> bool {
+    let mut size = self.size;
+    size.resize(physical) && self.apply(size)
+  }
+
+  // For `WindowEvent::ScaleFactorChanged`, the physical size follows with the `Resized` winit
+  // sends after it. Only the logical size changes until then.
+  pub fn set_scale_factor(&mut self, scale_factor: f64) {
+    if self.size.set_scale_factor(scale_factor) {
+      log::info!("[resize]: scale factor {}", scale_factor);
+    }
+  }
+
+  // Draws scenes at a multiple of the surface size, scaled to it with a linear filter, e.g.
+  // 2.0 to supersample or 0.5 for slow GPUs. Above 2.0 the filter skips pixels and aliases.
+  pub fn set_render_scale(&mut self, render_scale: f32) -> bool {
+    let mut size = self.size;
+    size.set_render_scale(render_scale);
+    self.apply(size)
+  }
+
+  fn apply(&mut self, size: SurfaceSize) -> bool {
+    let old = std::mem::replace(&mut self.size, size);
+    if size.physical() != old.physical() {
+      let physical = size.physical();
+      log::info!("[resize]: width {} height {} scale factor {}", physical.width, physical.height, size.scale_factor());
+      self.config.width = physical.width;
+      self.config.height = physical.height;
+      self.surface.configure(&self.device.gpu().device, &self.config);
+    }
+
+    let (width, height) = size.render_size();
+    let device = &self.device.gpu().device;
+    if !size.is_scaled() {
+      self.scaled = None;
+    } else if let Some(scaled) = &mut self.scaled {
+      scaled.resize(device, self.policy.render_format, width, height);
+    } else {
+      self.scaled = Some(ScaledTarget::new(device, self.policy.render_format, width, height));
+    }
+    if size.render_size() != old.render_size() {
+      if size.is_render_scale_clamped() {
+        log::warn!(
+          "[resize]: render scale {} exceeds the largest texture of {}, using {}",
+          size.render_scale(), self.device.gpu().device.limits().max_texture_dimension_2d, size.effective_render_scale()
+        );
+      }
+      log::info!("[resize]: rendering at {}x{} (scale {})", width, height, size.effective_render_scale());
+      return true;
+    }
+    false
+  }
+
+  // When the surface can be tried again, while frames are skipped after failed attempts.
+  pub fn retry_at(&self) -> Option<Instant> {
+    self.recovery.retry_at()
+  }
+
+  // Draws and presents a frame. Lost and outdated surfaces are reconfigured and frames that
+  // can't be acquired are skipped, so only errors the app can't continue after are returned.
+  pub fn render(&mut self, scene: &mut dyn Scene) -> Result<(), wgpu::SurfaceError> {
+    let gpu = self.device.gpu();
+    let mut surface = WindowSurface { surface: &self.surface, device: &gpu.device, config: &self.config };
+    let mut events = Vec::new();
+    let acquired = self.recovery.acquire(&mut surface, Instant::now(), &mut |event| events.push(event));
+    for event in &events {
+      log_event(event);
+      scene.surface_event(event);
+    }
+    let output = match acquired {
+      Acquired::Frame(output) => output,
+      Acquired::Skip => return Ok(()),
+      Acquired::Fatal(e) => return Err(e),
+    };
+    self.profiler.begin_frame(&gpu.device, Instant::now());
+    let timer = self.profiler.gpu_timer();
+    let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+      format: Some(self.policy.render_format),
+      ..Default::default()
+    });
+
+    let mut encoder = gpu.device.create_command_encoder(
+      &wgpu::CommandEncoderDescriptor {
+        label: Some("Render Encoder")
+      }
+    );
+
+    let (width, height) = self.size();
+    match &self.scaled {
+      Some(scaled) => {
+        scene.render(&Frame { gpu, view: scaled.view(), format: self.format(), width, height, timer }, &mut encoder);
+        scaled.encode(&mut encoder, &view, timer);
+      }
+      None => scene.render(&Frame { gpu, view: &view, format: self.format(), width, height, timer }, &mut encoder),
+    }
+    self.profiler.resolve(&mut encoder);
+
+    // the copy is submitted with the frame, so the capture is exactly what gets presented
+    #[cfg(not(target_arch = "wasm32"))]
+    if std::mem::take(&mut self.capture_next) {
+      let path = capture::capture_path();
+      let frame = capture::read_texture(&gpu.device, &gpu.queue, encoder, &output.texture, &self.policy);
+      match frame.and_then(|frame| frame.save_png(&path)) {
+        Ok(_) => log::info!("[capture]: saved {}", path),
+        Err(e) => log::error!("[capture]: {}", e),
+      }
+    } else {
+      gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+    #[cfg(target_arch = "wasm32")]
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+    output.present();
+    self.profiler.end_frame();
+
+    Ok(())
+  }
+
+  // Whether `save_capture` copies the presented frame out of the surface.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn can_copy_surface(&self) -> bool {
+    self.config.usage.contains(wgpu::TextureUsages::COPY_SRC)
+  }
+
+  // The fallback for surfaces without `COPY_SRC`: renders the scene again into an offscreen
+  // copy of the surface and reads it back. This costs another frame and may not match the
+  // presented one exactly, e.g. for scenes that animate in `render`.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn capture(&self, scene: &dyn Scene) -> Result<CapturedFrame, CaptureError> {
+    capture::capture_scene(self.gpu(), scene, &self.policy, self.size)
+  }
+
+  // Saves the next presented frame, copied out of the surface texture, as a PNG in the working
+  // directory. Without `COPY_SRC` on the surface the scene is captured with `capture` instead.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn save_capture(&mut self, scene: &dyn Scene) {
+    if self.can_copy_surface() {
+      self.capture_next = true;
+      self.window.request_redraw();
+      return;
+    }
+    let path = capture::capture_path();
+    match self.capture(scene).and_then(|frame| frame.save_png(&path)) {
+      Ok(_) => log::info!("[capture]: saved {}", path),
+      Err(e) => log::error!("[capture]: {}", e),
+    }
+  }
+}
@@ -0,0 +1,42 @@
+use renderer::{
+  capture::{can_copy_surface, create_capture_texture, read_texture},
+  ClearNode, ColorPolicy, ColorSpace, Frame, Gpu, RenderNode,
+};
+use wgpu::{SurfaceCapabilities, TextureFormat, TextureUsages};
+
+#[test]
+fn surfaces_are_copied_only_with_copy_src_and_a_readable_format() {
+  let copyable = SurfaceCapabilities {
+    usages: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    ..Default::default()
+  };
+  assert!(can_copy_surface(&copyable, TextureFormat::Bgra8UnormSrgb));
+  assert!(can_copy_surface(&copyable, TextureFormat::Rgba8Unorm));
+  // re-rendered into an RGBA8 target instead
+  assert!(!can_copy_surface(&copyable, TextureFormat::Rgb10a2Unorm));
+
+  let render_only = SurfaceCapabilities { usages: TextureUsages::RENDER_ATTACHMENT, ..Default::default() };
+  assert!(!can_copy_surface(&render_only, TextureFormat::Bgra8UnormSrgb));
+}
+
+#[test]
+fn frames_the_final_pass_encoded_arent_encoded_again() {
+  let gpu = pollster::block_on(Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults())).unwrap();
+  let format = TextureFormat::Rgba8Unorm;
+  // stands in for a pass that wrote 50% grey, already sRGB encoded or linear
+  let grey = wgpu::Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 };
+  let capture = |policy: &ColorPolicy| {
+    let texture = create_capture_texture(&gpu.device, 4, 4, format);
+    let view = texture.create_view(&Default::default());
+    let mut encoder = gpu.device.create_command_encoder(&Default::default());
+    let frame = Frame { gpu: &gpu, view: &view, format, width: 4, height: 4, timer: None };
+    ClearNode::new(grey).encode(&frame, &mut encoder);
+    read_texture(&gpu.device, &gpu.queue, encoder, &texture, policy).unwrap().pixels[0]
+  };
+
+  let encoded = ColorPolicy::for_format(format, ColorSpace::Srgb);
+  assert!(encoded.encode_srgb);
+  assert!((127..=129).contains(&capture(&encoded)));
+  // linear values are encoded for the PNG
+  assert!((187..=189).contains(&capture(&ColorPolicy::for_format(format, ColorSpace::Linear))));
+}
@@ -0,0 +1,55 @@
+use renderer::{ColorPolicy, ColorSpace, SurfaceSupport};
+use wgpu::TextureFormat;
+
+const NATIVE: SurfaceSupport = SurfaceSupport { srgb_formats: true, view_formats: true };
+const WEBGPU: SurfaceSupport = SurfaceSupport { srgb_formats: false, view_formats: true };
+const WEBGL: SurfaceSupport = SurfaceSupport { srgb_formats: true, view_formats: false };
+
+#[test]
+fn policy_picks_formats_for_the_color_space() {
+  let formats = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm];
+
+  let srgb = ColorPolicy::new(&formats, ColorSpace::Srgb, NATIVE);
+  assert_eq!(srgb.surface_format, TextureFormat::Bgra8UnormSrgb);
+  assert_eq!(srgb.view_formats, [TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm]);
+  assert_eq!(srgb.render_format, TextureFormat::Bgra8UnormSrgb);
+  assert!(!srgb.encode_srgb);
+
+  let linear = ColorPolicy::new(&formats, ColorSpace::Linear, NATIVE);
+  assert_eq!(linear.surface_format, TextureFormat::Bgra8Unorm);
+  assert_eq!(linear.render_format, TextureFormat::Bgra8Unorm);
+  assert!(!linear.encode_srgb);
+  assert!(!linear.output_is_srgb());
+
+  // only the sRGB format, but a linear view of it
+  let linear = ColorPolicy::new(&formats[..1], ColorSpace::Linear, NATIVE);
+  assert_eq!(linear.surface_format, TextureFormat::Bgra8UnormSrgb);
+  assert_eq!(linear.render_format, TextureFormat::Bgra8Unorm);
+}
+
+#[test]
+fn policy_encodes_srgb_when_the_surface_cant() {
+  let formats = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm];
+
+  // an sRGB view of a linear surface does the encoding
+  let web = ColorPolicy::new(&formats, ColorSpace::Srgb, WEBGPU);
+  assert_eq!(web.surface_format, TextureFormat::Bgra8Unorm);
+  assert_eq!(web.render_format, TextureFormat::Bgra8UnormSrgb);
+  assert!(!web.encode_srgb);
+
+  // no views and only linear formats, so the shader has to
+  let web = ColorPolicy::new(&formats, ColorSpace::Srgb, SurfaceSupport { view_formats: false, ..WEBGPU });
+  assert_eq!(web.render_format, TextureFormat::Bgra8Unorm);
+  assert!(web.view_formats.is_empty());
+  assert!(web.encode_srgb);
+  assert!(web.output_is_srgb());
+
+  let webgl = ColorPolicy::new(&[TextureFormat::Rgba8Unorm], ColorSpace::Srgb, WEBGL);
+  assert_eq!(webgl.render_format, TextureFormat::Rgba8Unorm);
+  assert!(webgl.encode_srgb);
+
+  // linear wanted but only sRGB possible
+  let webgl = ColorPolicy::new(&[TextureFormat::Rgba8UnormSrgb], ColorSpace::Linear, WEBGL);
+  assert_eq!(webgl.render_format, TextureFormat::Rgba8UnormSrgb);
+  assert!(!webgl.encode_srgb);
+}
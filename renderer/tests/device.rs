@@ -0,0 +1,140 @@
+use renderer::{validate, DeviceError, DeviceLifecycle, Gpu};
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = "
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+@compute @workgroup_size(1)
+fn double(@builtin(global_invocation_id) id: vec3<u32>) {
+  data[id.x] = data[id.x] * 2u;
+}
+";
+
+fn lifecycle() -> DeviceLifecycle {
+  let limits = wgpu::Limits::downlevel_defaults();
+  let gpu = pollster::block_on(Gpu::headless(limits.clone())).unwrap();
+  DeviceLifecycle::new(gpu, "Test Device", limits)
+}
+
+fn words(bytes: &[u8]) -> Vec<u32> {
+  bytes.chunks_exact(4).map(|b| u32::from_le_bytes(b.try_into().unwrap())).collect()
+}
+
+fn bytes(words: &[u32]) -> Vec<u8> {
+  words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+// What a scene would own, created again on every new device.
+struct Doubler {
+  data: wgpu::Buffer,
+  pipeline: wgpu::ComputePipeline,
+  bind_group: wgpu::BindGroup,
+}
+
+fn doubler(device: &wgpu::Device, input: &[u32]) -> Doubler {
+  let data = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    label: Some("Data"),
+    contents: &bytes(input),
+    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+  });
+  let pipeline = validate(device, "Double Pipeline", || double_pipeline(device, "double")).unwrap();
+  let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: Some("Double Bind Group"),
+    layout: &pipeline.get_bind_group_layout(0),
+    entries: &[wgpu::BindGroupEntry { binding: 0, resource: data.as_entire_binding() }],
+  });
+  Doubler { data, pipeline, bind_group }
+}
+
+fn double_pipeline(device: &wgpu::Device, entry_point: &str) -> wgpu::ComputePipeline {
+  let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("Double Shader"),
+    source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+  });
+  device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+    label: Some("Double Pipeline"),
+    layout: None,
+    module: &module,
+    entry_point,
+  })
+}
+
+// Doubles the data on the GPU and reads it back.
+fn run(gpu: &Gpu, doubler: &Doubler, len: u32) -> Vec<u32> {
+  let size = len as u64 * 4;
+  let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Staging"),
+    size,
+    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  let mut encoder = gpu.device.create_command_encoder(&Default::default());
+  {
+    let mut pass = encoder.begin_compute_pass(&Default::default());
+    pass.set_pipeline(&doubler.pipeline);
+    pass.set_bind_group(0, &doubler.bind_group, &[]);
+    pass.dispatch_workgroups(len, 1, 1);
+  }
+  encoder.copy_buffer_to_buffer(&doubler.data, 0, &staging, 0, size);
+  gpu.queue.submit(Some(encoder.finish()));
+
+  staging.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+  gpu.device.poll(wgpu::Maintain::Wait);
+  let out = words(&staging.slice(..).get_mapped_range());
+  out
+}
+
+#[test]
+fn validation_errors_are_returned_instead_of_reaching_the_device() {
+  let device = lifecycle();
+  let gpu = device.gpu();
+  let doubler = doubler(&gpu.device, &[1]);
+
+  let missing = validate(&gpu.device, "Missing Entry Point", || double_pipeline(&gpu.device, "missing"));
+  assert!(matches!(missing, Err(DeviceError::Validation { ref label, .. }) if label == "Missing Entry Point"));
+
+  // the failed pipeline neither reached the uncaptured handler nor broke what was there
+  assert!(device.take_errors().is_empty());
+  assert_eq!(run(gpu, &doubler, 1), [2]);
+}
+
+#[test]
+fn uncaptured_errors_are_kept_instead_of_panicking() {
+  let device = lifecycle();
+  device.gpu().device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Invalid"),
+    size: 4,
+    // mapping for reading only goes together with copies
+    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  let errors = device.take_errors();
+  assert_eq!(errors.len(), 1);
+  assert!(matches!(errors[0], DeviceError::Uncaptured(_)));
+  assert!(device.take_errors().is_empty());
+  assert!(!device.is_lost());
+}
+
+#[test]
+fn lost_devices_are_recreated_for_the_scene_to_rebuild() {
+  let mut device = lifecycle();
+  assert!(!pollster::block_on(device.recover()).unwrap());
+  assert_eq!(device.generation(), 0);
+
+  device.gpu().device.destroy();
+  device.gpu().device.poll(wgpu::Maintain::Wait);
+  assert!(device.is_lost());
+
+  assert!(pollster::block_on(device.recover()).unwrap());
+  assert!(!device.is_lost());
+  assert_eq!(device.generation(), 1);
+  // nothing else to recover until the new device is lost too
+  assert!(!pollster::block_on(device.recover()).unwrap());
+
+  // what was built on the old device is built again on the new one
+  let doubler = doubler(&device.gpu().device, &[1, 2, 3]);
+  assert_eq!(run(device.gpu(), &doubler, 3), [2, 4, 6]);
+  assert!(device.take_errors().is_empty());
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use renderer::{frame_interval, parse_present_mode, select_present_mode, FrameLimiter, Instant};
+use wgpu::PresentMode;
+
+#[test]
+fn present_modes_parse() {
+  assert_eq!(parse_present_mode("fifo"), Ok(PresentMode::Fifo));
+  assert_eq!(parse_present_mode("Fifo-Relaxed"), Ok(PresentMode::FifoRelaxed));
+  assert_eq!(parse_present_mode("auto_no_vsync"), Ok(PresentMode::AutoNoVsync));
+  assert!(parse_present_mode("vsync").is_err());
+}
+
+#[test]
+fn unsupported_present_modes_fall_back() {
+  let supported = [PresentMode::Fifo, PresentMode::Mailbox];
+  assert_eq!(select_present_mode(PresentMode::Mailbox, &supported), PresentMode::Mailbox);
+  // no tearing without vsync, but no waiting either
+  assert_eq!(select_present_mode(PresentMode::Immediate, &supported), PresentMode::Mailbox);
+  assert_eq!(select_present_mode(PresentMode::FifoRelaxed, &supported), PresentMode::Fifo);
+  assert_eq!(select_present_mode(PresentMode::Immediate, &[PresentMode::Fifo]), PresentMode::Fifo);
+  // resolved by wgpu
+  assert_eq!(select_present_mode(PresentMode::AutoNoVsync, &[]), PresentMode::AutoNoVsync);
+}
+
+#[test]
+fn unlimited_without_a_target() {
+  // the interval of the tiny rate doesn't fit a `Duration`
+  for target in [None, Some(0.0), Some(-30.0), Some(f64::INFINITY), Some(f64::NAN), Some(1e-30)] {
+    let mut limiter = FrameLimiter::new(target);
+    assert_eq!(limiter.interval(), None);
+    assert_eq!(limiter.frame_done(Instant::now()), None);
+  }
+}
+
+#[test]
+fn intervals_only_exist_for_positive_rates() {
+  assert_eq!(frame_interval(50.0), Some(Duration::from_millis(20)));
+  assert_eq!(frame_interval(1e-30), None);
+  assert_eq!(frame_interval(f64::MIN_POSITIVE), None);
+  assert_eq!(frame_interval(-1.0), None);
+}
+
+#[test]
+fn frames_are_paced_without_drifting() {
+  let ms = Duration::from_millis(1);
+  let start = Instant::now();
+  let mut limiter = FrameLimiter::new(Some(50.0));
+  assert_eq!(limiter.interval(), Some(20 * ms));
+
+  assert_eq!(limiter.frame_done(start), Some(start + 20 * ms));
+  // a little late, the next frame makes up for it
+  assert_eq!(limiter.frame_done(start + 23 * ms), Some(start + 40 * ms));
+  // drawn early for a resize, the schedule stays
+  assert_eq!(limiter.frame_done(start + 30 * ms), Some(start + 40 * ms));
+  assert_eq!(limiter.frame_done(start + 40 * ms), Some(start + 60 * ms));
+  // too late to catch up, start over
+  assert_eq!(limiter.frame_done(start + 95 * ms), Some(start + 115 * ms));
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use renderer::{pass_timings, ClearNode, Frame, FrameTimer, Gpu, GpuTimer, Instant, PassTiming, RenderNode};
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn frame_times_are_averaged_over_the_window() {
+  let start = Instant::now();
+  let mut timer = FrameTimer::new(3);
+  assert_eq!(timer.stats().fps, 0.0);
+
+  timer.tick(start);
+  assert_eq!(timer.stats().frames, 1);
+  assert_eq!(timer.stats().average, Duration::ZERO);
+
+  // 10, 20, 30 and then 40 ms, the first drops out of the window
+  let mut now = start;
+  for frame_time in [10, 20, 30, 40] {
+    now += frame_time * MS;
+    timer.tick(now);
+  }
+  let stats = timer.stats();
+  assert_eq!(stats.frames, 5);
+  assert_eq!(stats.frame_time, 40 * MS);
+  assert_eq!(stats.average, 30 * MS);
+  assert!((stats.fps - 1000.0 / 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn timestamps_become_pass_durations() {
+  let labels = ["Scene".to_string(), "Blur".to_string()];
+  // the second pass ended before it began, e.g. the counter wrapped
+  let timings = pass_timings(&labels, &[100, 1100, 5000, 10], 2.5);
+  assert_eq!(timings, [
+    PassTiming { label: "Scene".to_string(), duration: Duration::from_nanos(2500) },
+    PassTiming { label: "Blur".to_string(), duration: Duration::ZERO },
+  ]);
+  // timestamps of passes that weren't recorded are ignored
+  assert_eq!(pass_timings(&labels[..1], &[0, 4, 0, 0], 1.0).len(), 1);
+}
+
+#[test]
+fn passes_are_timed_where_timestamps_are_supported() {
+  let gpu = pollster::block_on(Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults())).unwrap();
+  let texture = renderer::capture::create_capture_texture(&gpu.device, 4, 4, wgpu::TextureFormat::Rgba8UnormSrgb);
+  let view = texture.create_view(&Default::default());
+  let frame = Frame { gpu: &gpu, view: &view, format: texture.format(), width: 4, height: 4, timer: None };
+  assert!(frame.timestamp_writes("Clear Pass").is_none());
+
+  let Some(mut timer) = GpuTimer::new(&gpu) else {
+    eprintln!("adapter has no timestamp queries, skipping");
+    return;
+  };
+  // nothing is recorded outside of a frame
+  assert!(timer.render_pass_writes("Clear Pass").is_none());
+
+  timer.begin_frame(&gpu.device);
+  let mut encoder = gpu.device.create_command_encoder(&Default::default());
+  ClearNode::new(wgpu::Color::RED).encode(&Frame { timer: Some(&timer), ..frame }, &mut encoder);
+  timer.resolve(&mut encoder);
+  gpu.queue.submit(Some(encoder.finish()));
+  timer.end_frame();
+
+  gpu.device.poll(wgpu::Maintain::Wait);
+  timer.begin_frame(&gpu.device);
+  assert_eq!(timer.timings().len(), 1);
+  assert_eq!(timer.timings()[0].label, "Clear Pass");
+}
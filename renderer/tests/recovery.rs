@@ -0,0 +1,163 @@
+use std::{collections::VecDeque, time::Duration};
+
+use renderer::{Acquired, FrameSurface, Instant, RecoveryPolicy, SurfaceEvent, SurfaceRecovery};
+use wgpu::SurfaceError;
+
+// Hands out the scripted results in order, frames are numbered.
+#[derive(Default)]
+struct MockSurface {
+  results: VecDeque<Result<u32, SurfaceError>>,
+  acquired: u32,
+  reconfigured: u32,
+}
+
+impl MockSurface {
+  fn new(results: impl IntoIterator<Item = Result<u32, SurfaceError>>) -> Self {
+    Self { results: results.into_iter().collect(), ..Default::default() }
+  }
+}
+
+impl FrameSurface for MockSurface {
+  type Frame = u32;
+
+  fn acquire(&mut self) -> Result<u32, SurfaceError> {
+    self.acquired += 1;
+    self.results.pop_front().expect("acquired more often than scripted")
+  }
+
+  fn reconfigure(&mut self) {
+    self.reconfigured += 1;
+  }
+}
+
+const MS: Duration = Duration::from_millis(1);
+
+fn policy() -> RecoveryPolicy {
+  RecoveryPolicy { max_attempts: 4, initial_backoff: 10 * MS, max_backoff: 25 * MS }
+}
+
+// Runs one acquire and returns what it gave with the events it reported.
+fn acquire(recovery: &mut SurfaceRecovery, surface: &mut MockSurface, now: Instant) -> (Acquired<u32>, Vec<SurfaceEvent>) {
+  let mut events = Vec::new();
+  let acquired = recovery.acquire(surface, now, &mut |event| events.push(event));
+  (acquired, events)
+}
+
+#[test]
+fn lost_and_outdated_surfaces_are_reconfigured() {
+  let now = Instant::now();
+  let mut recovery = SurfaceRecovery::new(policy());
+  let mut surface = MockSurface::new([Ok(1), Err(SurfaceError::Lost), Ok(2), Err(SurfaceError::Outdated), Ok(3)]);
+
+  let (acquired, events) = acquire(&mut recovery, &mut surface, now);
+  assert!(matches!(acquired, Acquired::Frame(1)));
+  assert!(events.is_empty());
+
+  // reconfigured and acquired again within the same frame
+  for (frame, error) in [(2, SurfaceError::Lost), (3, SurfaceError::Outdated)] {
+    let (acquired, events) = acquire(&mut recovery, &mut surface, now);
+    assert!(matches!(acquired, Acquired::Frame(f) if f == frame));
+    assert_eq!(events, [SurfaceEvent::Reconfigured(error)]);
+  }
+  assert_eq!(surface.reconfigured, 2);
+  assert_eq!(recovery.failures(), 0);
+}
+
+#[test]
+fn timeouts_skip_the_frame() {
+  let now = Instant::now();
+  let mut recovery = SurfaceRecovery::new(policy());
+  let mut surface = MockSurface::new([Err(SurfaceError::Timeout), Ok(1)]);
+
+  let (acquired, events) = acquire(&mut recovery, &mut surface, now);
+  assert!(matches!(acquired, Acquired::Skip));
+  assert_eq!(events, [SurfaceEvent::FrameSkipped(SurfaceError::Timeout)]);
+  assert_eq!(surface.reconfigured, 0);
+  assert_eq!(recovery.retry_at(), None);
+
+  let (acquired, _) = acquire(&mut recovery, &mut surface, now);
+  assert!(matches!(acquired, Acquired::Frame(1)));
+}
+
+#[test]
+fn failed_reconfigures_back_off_until_recovered() {
+  let start = Instant::now();
+  let mut recovery = SurfaceRecovery::new(policy());
+  let mut surface = MockSurface::new([
+    Err(SurfaceError::Lost), Err(SurfaceError::Lost),
+    Err(SurfaceError::Lost), Err(SurfaceError::Outdated),
+    Err(SurfaceError::Outdated), Ok(1),
+  ]);
+
+  let (acquired, events) = acquire(&mut recovery, &mut surface, start);
+  assert!(matches!(acquired, Acquired::Skip));
+  assert_eq!(events, [
+    SurfaceEvent::Reconfigured(SurfaceError::Lost),
+    SurfaceEvent::Backoff { attempt: 1, delay: 10 * MS },
+  ]);
+  assert_eq!(recovery.retry_at(), Some(start + 10 * MS));
+
+  // frames during the backoff don't touch the surface
+  let (acquired, events) = acquire(&mut recovery, &mut surface, start + 5 * MS);
+  assert!(matches!(acquired, Acquired::Skip));
+  assert!(events.is_empty());
+  assert_eq!(surface.acquired, 2);
+
+  let (_, events) = acquire(&mut recovery, &mut surface, start + 10 * MS);
+  assert_eq!(events[1], SurfaceEvent::Backoff { attempt: 2, delay: 20 * MS });
+  assert_eq!(recovery.retry_at(), Some(start + 30 * MS));
+
+  let (acquired, events) = acquire(&mut recovery, &mut surface, start + 30 * MS);
+  assert!(matches!(acquired, Acquired::Frame(1)));
+  assert_eq!(events, [
+    SurfaceEvent::Reconfigured(SurfaceError::Outdated),
+    SurfaceEvent::Recovered { attempts: 2 },
+  ]);
+  assert_eq!(recovery.retry_at(), None);
+  assert_eq!(recovery.failures(), 0);
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+  let mut now = Instant::now();
+  let mut recovery = SurfaceRecovery::new(policy());
+  let mut surface = MockSurface::new(std::iter::repeat_n(Err(SurfaceError::Lost), 8));
+
+  let mut delays = Vec::new();
+  let fatal = loop {
+    let (acquired, events) = acquire(&mut recovery, &mut surface, now);
+    delays.extend(events.iter().filter_map(|event| match event {
+      SurfaceEvent::Backoff { delay, .. } => Some(*delay),
+      _ => None,
+    }));
+    if let Acquired::Fatal(error) = acquired {
+      assert_eq!(events.last(), Some(&SurfaceEvent::Fatal(SurfaceError::Lost)));
+      break error;
+    }
+    now = recovery.retry_at().unwrap();
+  };
+
+  assert_eq!(fatal, SurfaceError::Lost);
+  // doubled, then capped at `max_backoff`
+  assert_eq!(delays, [10 * MS, 20 * MS, 25 * MS]);
+  assert_eq!(surface.reconfigured, 4);
+}
+
+#[test]
+fn out_of_memory_is_fatal() {
+  let mut recovery = SurfaceRecovery::new(policy());
+  let mut surface = MockSurface::new([Err(SurfaceError::OutOfMemory)]);
+
+  let (acquired, events) = acquire(&mut recovery, &mut surface, Instant::now());
+  assert!(matches!(acquired, Acquired::Fatal(SurfaceError::OutOfMemory)));
+  assert_eq!(events, [SurfaceEvent::Fatal(SurfaceError::OutOfMemory)]);
+  assert_eq!(surface.reconfigured, 0);
+}
+
+#[test]
+fn backoff_saturates() {
+  let policy = RecoveryPolicy { max_attempts: u32::MAX, ..RecoveryPolicy::default() };
+  assert_eq!(policy.backoff(1), policy.initial_backoff);
+  assert_eq!(policy.backoff(2), policy.initial_backoff * 2);
+  assert_eq!(policy.backoff(100), policy.max_backoff);
+}
@@ -0,0 +1,55 @@
+use std::{cell::Cell, rc::Rc};
+
+use renderer::{ClearNode, Frame, Gpu, HeadlessRenderer, NodeScene, RenderNode, Scene};
+
+fn headless(width: u32, height: u32) -> HeadlessRenderer {
+  let gpu = pollster::block_on(Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults())).unwrap();
+  HeadlessRenderer::new(gpu, width, height)
+}
+
+// Counts how often it was encoded and remembers the last size it was given.
+#[derive(Clone, Default)]
+struct Probe {
+  encoded: Rc<Cell<u32>>,
+  size: Rc<Cell<(u32, u32)>>,
+}
+
+impl RenderNode for Probe {
+  fn resize(&mut self, _gpu: &Gpu, width: u32, height: u32) {
+    self.size.set((width, height));
+  }
+
+  fn encode(&self, frame: &Frame, _encoder: &mut wgpu::CommandEncoder) {
+    assert_eq!(frame.format, HeadlessRenderer::FORMAT);
+    assert_eq!(self.size.get(), (frame.width, frame.height));
+    self.encoded.set(self.encoded.get() + 1);
+  }
+}
+
+#[test]
+fn nodes_run_in_order_and_follow_the_size() {
+  let mut renderer = headless(16, 8);
+
+  // the second clear wins; sRGB target, so 0.5 linear is stored as 188
+  let mut scene = NodeScene::new()
+    .with(ClearNode::new(wgpu::Color::RED))
+    .with(ClearNode::new(wgpu::Color { r: 0.0, g: 0.5, b: 1.0, a: 1.0 }));
+  assert_eq!(scene.len(), 2);
+
+  let frame = renderer.capture(&scene).unwrap();
+  assert_eq!((frame.width, frame.height), (16, 8));
+  for pixel in frame.pixels.chunks(4) {
+    assert_eq!(pixel[0], 0);
+    assert!(pixel[1].abs_diff(188) <= 1, "{pixel:?}");
+    assert_eq!(&pixel[2..], [255, 255]);
+  }
+
+  let probe = Probe::default();
+  scene.push(probe.clone());
+  renderer.resize(4, 2);
+  scene.resize(renderer.gpu(), 4, 2);
+  let frame = renderer.capture(&scene).unwrap();
+  assert_eq!(frame.pixels.len(), 4 * 2 * 4);
+  renderer.capture(&scene).unwrap();
+  assert_eq!(probe.encoded.get(), 2);
+}
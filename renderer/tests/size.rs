@@ -0,0 +1,108 @@
+use std::{cell::Cell, rc::Rc};
+
+use renderer::{ClearNode, Frame, Gpu, HeadlessRenderer, NodeScene, RenderNode, SurfaceSize};
+use winit::dpi::{LogicalSize, PhysicalSize};
+
+#[test]
+fn logical_size_follows_the_scale_factor() {
+  let mut size = SurfaceSize::new(PhysicalSize::new(1600, 1200), 2.0);
+  assert_eq!(size.logical(), LogicalSize::new(800.0, 600.0));
+  assert_eq!(size.render_size(), (1600, 1200));
+
+  // the physical size only changes with the resize that follows
+  assert!(size.set_scale_factor(1.5));
+  assert!(!size.set_scale_factor(1.5));
+  assert_eq!(size.physical(), PhysicalSize::new(1600, 1200));
+  assert!(size.resize(PhysicalSize::new(1200, 900)));
+  assert_eq!(size.logical(), LogicalSize::new(800.0, 600.0));
+}
+
+#[test]
+fn zero_sizes_are_ignored() {
+  let mut size = SurfaceSize::new(PhysicalSize::new(0, 0), 1.0);
+  assert_eq!(size.physical(), PhysicalSize::new(1, 1));
+
+  assert!(size.resize(PhysicalSize::new(640, 480)));
+  assert!(!size.resize(PhysicalSize::new(640, 480)));
+  assert!(!size.resize(PhysicalSize::new(0, 480)));
+  assert_eq!(size.physical(), PhysicalSize::new(640, 480));
+}
+
+#[test]
+fn render_scale_rounds_and_clamps() {
+  let size = SurfaceSize::new(PhysicalSize::new(101, 3), 1.0);
+  assert!(!size.is_scaled());
+
+  let half = size.with_render_scale(0.5);
+  assert!(half.is_scaled());
+  assert_eq!(half.render_size(), (51, 2));
+  assert_eq!(half.physical(), size.physical());
+  assert_eq!(size.with_render_scale(2.0).render_size(), (202, 6));
+
+  assert_eq!(size.with_render_scale(100.0).render_scale(), SurfaceSize::MAX_RENDER_SCALE);
+  assert_eq!(size.with_render_scale(0.0).render_scale(), SurfaceSize::MIN_RENDER_SCALE);
+  assert_eq!(size.with_render_scale(0.01).render_size(), (13, 1));
+  assert_eq!(size.with_render_scale(f32::NAN).render_scale(), 1.0);
+}
+
+#[test]
+fn render_size_stays_within_the_largest_texture() {
+  let size = SurfaceSize::new(PhysicalSize::new(1000, 500), 1.0).with_max_dimension(2048);
+  assert!(!size.with_render_scale(2.0).is_render_scale_clamped());
+  assert_eq!(size.with_render_scale(2.0).render_size(), (2000, 1000));
+
+  // the aspect ratio is kept
+  let clamped = size.with_render_scale(4.0);
+  assert!(clamped.is_render_scale_clamped());
+  assert_eq!(clamped.render_scale(), 4.0);
+  assert_eq!(clamped.render_size(), (2048, 1024));
+
+  // a surface at the limit isn't scaled down, nor is a scale below 1 raised
+  let large = SurfaceSize::new(PhysicalSize::new(4096, 100), 1.0).with_max_dimension(2048);
+  assert_eq!(large.with_render_scale(2.0).render_size(), (4096, 100));
+  assert_eq!(large.with_render_scale(0.25).render_size(), (1024, 25));
+}
+
+// Remembers the size of the frames it was drawn into.
+#[derive(Clone, Default)]
+struct FrameSize(Rc<Cell<(u32, u32)>>);
+
+impl RenderNode for FrameSize {
+  fn encode(&self, frame: &Frame, _encoder: &mut wgpu::CommandEncoder) {
+    self.0.set((frame.width, frame.height));
+  }
+}
+
+#[test]
+fn scaled_frames_are_captured_at_the_output_size() {
+  let gpu = pollster::block_on(Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults())).unwrap();
+  let mut renderer = HeadlessRenderer::new(gpu, 16, 8);
+  let frame_size = FrameSize::default();
+  let scene = NodeScene::new()
+    .with(ClearNode::new(wgpu::Color { r: 0.0, g: 0.5, b: 1.0, a: 1.0 }))
+    .with(frame_size.clone());
+
+  for (render_scale, drawn) in [(0.5, (8, 4)), (2.0, (32, 16)), (1.0, (16, 8))] {
+    renderer.set_render_scale(render_scale);
+    assert_eq!(renderer.size(), drawn);
+
+    let frame = renderer.capture(&scene).unwrap();
+    assert_eq!(frame_size.0.get(), drawn);
+    assert_eq!((frame.width, frame.height), (16, 8));
+    // a flat color survives the filter, and the sRGB round trip through the scaled target
+    for pixel in frame.pixels.chunks(4) {
+      assert_eq!(pixel, [0, 188, 255, 255]);
+    }
+  }
+}
+
+#[test]
+fn headless_render_sizes_follow_the_device_limits() {
+  let gpu = pollster::block_on(Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults())).unwrap();
+  // raised to what the adapter supports
+  let max = gpu.device.limits().max_texture_dimension_2d;
+  let mut renderer = HeadlessRenderer::new(gpu, max / 2, 4);
+
+  renderer.set_render_scale(SurfaceSize::MAX_RENDER_SCALE);
+  assert_eq!(renderer.size(), (max, 8));
+}
@@ -0,0 +1,37 @@
+use renderer::{CaptureError, CapturedFrame, Gpu, HeadlessRenderer, NodeScene};
+
+use crate::clear_scene;
+
+// Renders the clear-color scene into an offscreen texture instead of a window
+// surface, so it can run in CI and on machines without a display or GPU.
+pub struct HeadlessState {
+  renderer: HeadlessRenderer,
+  scene: NodeScene,
+}
+
+impl HeadlessState {
+  pub const FORMAT: wgpu::TextureFormat = HeadlessRenderer::FORMAT;
+
+  pub async fn new(width: u32, height: u32) -> Self {
+    let gpu = Gpu::headless(wgpu::Limits::downlevel_webgl2_defaults()).await
+      .expect("no adapter available for headless rendering");
+    Self { renderer: HeadlessRenderer::new(gpu, width, height), scene: clear_scene() }
+  }
+
+  pub fn get_adapter_info(&self) -> wgpu::AdapterInfo {
+    self.renderer.gpu().adapter_info()
+  }
+
+  pub fn size(&self) -> (u32, u32) {
+    self.renderer.size()
+  }
+
+  // Renders one frame and returns it as tightly packed RGBA8 rows, top row first.
+  pub fn render(&self) -> Vec<u8> {
+    self.capture_frame().expect("failed to read back headless frame").pixels
+  }
+
+  pub fn capture_frame(&self) -> Result<CapturedFrame, CaptureError> {
+    self.renderer.capture(&self.scene)
+  }
+}
@@ -0,0 +1,18 @@
+use renderer::testing::{Golden, Tolerance};
+
+fn golden() -> Golden {
+  Golden::new(env!("CARGO_MANIFEST_DIR"), env!("CARGO_TARGET_TMPDIR"))
+}
+
+#[tokio::test]
+async fn clear_color_matches_reference() {
+  let state = surface::HeadlessState::new(64, 64).await;
+  let (width, height) = state.size();
+  let pixels = state.render();
+
+  // a plain clear has no edges, so every pixel has to match
+  golden().assert_matches("clear", width, height, &pixels, Tolerance {
+    per_channel: 1,
+    max_failing_pixels: 0,
+  });
+}
@@ -0,0 +1,241 @@
+use std::{collections::BTreeMap, fmt};
+
+use wgpu::util::DeviceExt;
+
+#[derive(Debug)]
+pub enum GpuMapError {
+  Parse(String),
+  Validation(String),
+  MissingEntryPoint(String),
+  // only `var<storage>` runtime-sized arrays can be filled from host slices
+  UnsupportedBinding { group: u32, binding: u32 },
+  // the kernel has to write exactly one `var<storage, read_write>` array
+  OutputCount(usize),
+  InputCount { expected: usize, got: usize },
+  // the array stride in the shader differs from the size of the host type
+  Stride { group: u32, binding: u32, shader: u32, host: usize },
+  TooManyWorkgroups { workgroups: u32, max: u32 },
+  Map(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for GpuMapError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GpuMapError::Parse(e) | GpuMapError::Validation(e) => write!(f, "{e}"),
+      GpuMapError::MissingEntryPoint(name) => write!(f, "no compute entry point named `{name}`"),
+      GpuMapError::UnsupportedBinding { group, binding } =>
+        write!(f, "@group({group}) @binding({binding}) is not a storage buffer holding a runtime-sized array"),
+      GpuMapError::OutputCount(count) => write!(f, "expected one read_write storage buffer for the output, found {count}"),
+      GpuMapError::InputCount { expected, got } => write!(f, "the kernel reads {expected} inputs but {got} were given"),
+      GpuMapError::Stride { group, binding, shader, host } =>
+        write!(f, "@group({group}) @binding({binding}) has an array stride of {shader} bytes but the host type is {host} bytes"),
+      GpuMapError::TooManyWorkgroups { workgroups, max } => write!(f, "{workgroups} workgroups exceed the limit of {max}"),
+      GpuMapError::Map(e) => write!(f, "failed to map the output buffer: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for GpuMapError {}
+
+// A storage buffer the entry point uses, as found by naga.
+struct StorageBinding {
+  group: u32,
+  binding: u32,
+  read_only: bool,
+  stride: u32,
+}
+
+// The storage buffers `entry` uses, sorted by group and binding, and its workgroup size.
+fn reflect(module: &naga::Module, source: &str, entry: &str) -> Result<(Vec<StorageBinding>, [u32; 3]), GpuMapError> {
+  let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+    .validate(module)
+    .map_err(|e| GpuMapError::Validation(e.emit_to_string(source)))?;
+
+  let (index, entry_point) = module.entry_points.iter().enumerate()
+    .find(|(_, ep)| ep.name == entry && ep.stage == naga::ShaderStage::Compute)
+    .ok_or_else(|| GpuMapError::MissingEntryPoint(entry.to_string()))?;
+  let uses = info.get_entry_point(index);
+
+  let mut bindings = Vec::new();
+  for (handle, global) in module.global_variables.iter() {
+    let Some(resource) = &global.binding else { continue };
+    if uses[handle].is_empty() {
+      continue;
+    }
+
+    let unsupported = GpuMapError::UnsupportedBinding { group: resource.group, binding: resource.binding };
+    let naga::AddressSpace::Storage { access } = global.space else { return Err(unsupported) };
+    let naga::TypeInner::Array { size: naga::ArraySize::Dynamic, stride, .. } = module.types[global.ty].inner else {
+      return Err(unsupported);
+    };
+    bindings.push(StorageBinding {
+      group: resource.group,
+      binding: resource.binding,
+      read_only: !access.contains(naga::StorageAccess::STORE),
+      stride,
+    });
+  }
+  bindings.sort_by_key(|b| (b.group, b.binding));
+
+  Ok((bindings, entry_point.workgroup_size))
+}
+
+fn check_stride(binding: &StorageBinding, host: usize) -> Result<(), GpuMapError> {
+  if binding.stride as usize != host {
+    return Err(GpuMapError::Stride { group: binding.group, binding: binding.binding, shader: binding.stride, host });
+  }
+  Ok(())
+}
+
+// Runs `entry` of the WGSL kernel `wgsl_src` once per element of the longest input and
+// returns the array it wrote, which has as many elements.
+//
+// The kernel's read-only `var<storage, read>` arrays are filled from `inputs` in
+// @group/@binding order and its one `var<storage, read_write>` array is the output.
+// Invocations are laid out along x, and the last workgroup can run past the end of the
+// data, so the kernel should check `arrayLength` before indexing. Blocks until the
+// result is read back.
+pub fn gpu_map<T: bytemuck::Pod, U: bytemuck::Pod>(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  wgsl_src: &str,
+  entry: &str,
+  inputs: &[&[T]],
+) -> Result<Vec<U>, GpuMapError> {
+  let module = naga::front::wgsl::parse_str(wgsl_src).map_err(|e| GpuMapError::Parse(e.emit_to_string(wgsl_src)))?;
+  let (bindings, workgroup_size) = reflect(&module, wgsl_src, entry)?;
+
+  let (input_bindings, output_bindings): (Vec<_>, Vec<_>) = bindings.iter().partition(|b| b.read_only);
+  let [output_binding] = output_bindings[..] else {
+    return Err(GpuMapError::OutputCount(output_bindings.len()));
+  };
+  if input_bindings.len() != inputs.len() {
+    return Err(GpuMapError::InputCount { expected: input_bindings.len(), got: inputs.len() });
+  }
+  for binding in &input_bindings {
+    check_stride(binding, std::mem::size_of::<T>())?;
+  }
+  check_stride(output_binding, std::mem::size_of::<U>())?;
+
+  let len = inputs.iter().map(|input| input.len()).max().unwrap_or(0);
+  if len == 0 {
+    return Ok(Vec::new());
+  }
+  let workgroups = (len as u32).div_ceil(workgroup_size[0]);
+  let max = device.limits().max_compute_workgroups_per_dimension;
+  if workgroups > max {
+    return Err(GpuMapError::TooManyWorkgroups { workgroups, max });
+  }
+
+  let mut input_data = inputs.iter();
+  let buffers: Vec<wgpu::Buffer> = bindings.iter().map(|binding| {
+    let size = if binding.read_only {
+      let data: &[u8] = bytemuck::cast_slice(input_data.next().unwrap());
+      if !data.is_empty() {
+        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+          label: Some("gpu_map Input Buffer"),
+          contents: data,
+          usage: wgpu::BufferUsages::STORAGE,
+        });
+      }
+      // bindings can't be empty, so an empty input still gets one element
+      binding.stride as usize
+    } else {
+      len * std::mem::size_of::<U>()
+    };
+    device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("gpu_map Buffer"),
+      size: size as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    })
+  }).collect();
+
+  // one layout per group up to the highest one, groups in between stay empty
+  let mut groups: BTreeMap<u32, Vec<(&StorageBinding, &wgpu::Buffer)>> = BTreeMap::new();
+  for (binding, buffer) in bindings.iter().zip(&buffers) {
+    groups.entry(binding.group).or_default().push((binding, buffer));
+  }
+  let group_count = groups.keys().last().map_or(0, |group| group + 1);
+  let layouts: Vec<wgpu::BindGroupLayout> = (0..group_count).map(|group| {
+    let entries: Vec<_> = groups.get(&group).into_iter().flatten().map(|(binding, _)| wgpu::BindGroupLayoutEntry {
+      binding: binding.binding,
+      visibility: wgpu::ShaderStages::COMPUTE,
+      ty: wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only: binding.read_only },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+      count: None,
+    }).collect();
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some("gpu_map Bind Group Layout"),
+      entries: &entries,
+    })
+  }).collect();
+  let bind_groups: Vec<wgpu::BindGroup> = layouts.iter().enumerate().map(|(group, layout)| {
+    let entries: Vec<_> = groups.get(&(group as u32)).into_iter().flatten().map(|(binding, buffer)| wgpu::BindGroupEntry {
+      binding: binding.binding,
+      resource: buffer.as_entire_binding(),
+    }).collect();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("gpu_map Bind Group"),
+      layout,
+      entries: &entries,
+    })
+  }).collect();
+
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("gpu_map Shader"),
+    source: wgpu::ShaderSource::Wgsl(wgsl_src.into()),
+  });
+  let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+    label: Some("gpu_map Pipeline Layout"),
+    bind_group_layouts: &layouts.iter().collect::<Vec<_>>(),
+    push_constant_ranges: &[],
+  });
+  let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+    label: Some("gpu_map Pipeline"),
+    layout: Some(&pipeline_layout),
+    module: &shader,
+    entry_point: entry,
+  });
+
+  let output_size = (len * std::mem::size_of::<U>()) as wgpu::BufferAddress;
+  let readback = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("gpu_map Readback Buffer"),
+    size: output_size,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+  let output_index = bindings.iter().position(|b| !b.read_only).unwrap();
+
+  let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+    label: Some("gpu_map Encoder"),
+  });
+  {
+    let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+      label: Some("gpu_map Pass"),
+      timestamp_writes: None,
+    });
+    compute_pass.set_pipeline(&pipeline);
+    for (group, bind_group) in bind_groups.iter().enumerate() {
+      compute_pass.set_bind_group(group as u32, bind_group, &[]);
+    }
+    compute_pass.dispatch_workgroups(workgroups, 1, 1);
+  }
+  encoder.copy_buffer_to_buffer(&buffers[output_index], 0, &readback, 0, output_size);
+  queue.submit(std::iter::once(encoder.finish()));
+
+  let slice = readback.slice(..);
+  let (sender, receiver) = std::sync::mpsc::channel();
+  slice.map_async(wgpu::MapMode::Read, move |result| {
+    let _ = sender.send(result);
+  });
+  device.poll(wgpu::Maintain::Wait);
+  receiver.recv().unwrap().map_err(GpuMapError::Map)?;
+
+  let output = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+  readback.unmap();
+  Ok(output)
+}
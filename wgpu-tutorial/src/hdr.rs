@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use wgpu::util::DeviceExt;
+
+use renderer::{ColorPolicy, Frame, Gpu, GpuTimer, RenderNode};
+
+// The curve that maps HDR values into [0, 1].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemap {
+  // x / (1 + x), keeps the hue but washes out highlights slowly
+  Reinhard,
+  // Narkowicz's fit of the ACES filmic curve
+  #[default]
+  Aces,
+}
+
+impl FromStr for Tonemap {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "reinhard" => Ok(Tonemap::Reinhard),
+      "aces" => Ok(Tonemap::Aces),
+      _ => Err(format!("unknown tonemap `{s}`, expected reinhard or aces")),
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapParams {
+  exposure: f32,
+  curve: u32,
+  encode_srgb: u32,
+  _padding: u32,
+}
+
+struct HdrTarget {
+  _texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+impl HdrTarget {
+  fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("HDR Texture"),
+      size: wgpu::Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: HdrPipeline::FORMAT,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Self { _texture: texture, view }
+  }
+}
+
+// The scene is drawn into a floating point texture with values past 1.0, then `encode`
+// tonemaps it into the view the `ColorPolicy` picked for the surface.
+pub struct HdrPipeline {
+  target: HdrTarget,
+  width: u32,
+  height: u32,
+  exposure: f32,
+  tonemap: Tonemap,
+  encode_srgb: bool,
+  params: wgpu::Buffer,
+  sampler: wgpu::Sampler,
+  pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+}
+
+impl HdrPipeline {
+  pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+  pub fn new(device: &wgpu::Device, width: u32, height: u32, policy: &ColorPolicy, tonemap: Tonemap) -> Self {
+    let target = HdrTarget::new(device, width, height);
+    let exposure = 1.0;
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Tonemap Params Buffer"),
+      contents: bytemuck::bytes_of(&TonemapParams {
+        exposure,
+        curve: tonemap as u32,
+        encode_srgb: policy.encode_srgb as u32,
+        _padding: 0,
+      }),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("HDR Sampler"),
+      ..Default::default()
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("tonemap.wgsl"),
+      source: wgpu::ShaderSource::Wgsl(include_str!("tonemap.wgsl").into()),
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+      label: Some("Tonemap Pipeline"),
+      layout: None,
+      vertex: wgpu::VertexState {
+        module: &shader,
+        entry_point: "vs_main",
+        buffers: &[],
+      },
+      fragment: Some(wgpu::FragmentState {
+        module: &shader,
+        entry_point: "fs_main",
+        targets: &[Some(policy.render_format.into())],
+      }),
+      primitive: wgpu::PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: wgpu::MultisampleState::default(),
+      multiview: None,
+    });
+    let bind_group = Self::bind_group(device, &pipeline, &target, &sampler, &params);
+
+    Self {
+      target,
+      width,
+      height,
+      exposure,
+      tonemap,
+      encode_srgb: policy.encode_srgb,
+      params,
+      sampler,
+      pipeline,
+      bind_group,
+    }
+  }
+
+  fn bind_group(
+    device: &wgpu::Device,
+    pipeline: &wgpu::RenderPipeline,
+    target: &HdrTarget,
+    sampler: &wgpu::Sampler,
+    params: &wgpu::Buffer,
+  ) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Tonemap Bind Group"),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&target.view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(sampler),
+        },
+        wgpu::BindGroupEntry {
+          binding: 2,
+          resource: params.as_entire_binding(),
+        },
+      ],
+    })
+  }
+
+  // Where the scene is drawn, in `FORMAT`.
+  pub fn view(&self) -> &wgpu::TextureView {
+    &self.target.view
+  }
+
+  pub fn tonemap(&self) -> Tonemap {
+    self.tonemap
+  }
+
+  pub fn set_tonemap(&mut self, queue: &wgpu::Queue, tonemap: Tonemap) {
+    self.tonemap = tonemap;
+    self.write_params(queue);
+  }
+
+  pub fn exposure(&self) -> f32 {
+    self.exposure
+  }
+
+  // Scales the HDR values before the curve, 1.0 leaves them as they are.
+  pub fn set_exposure(&mut self, queue: &wgpu::Queue, exposure: f32) {
+    self.exposure = exposure;
+    self.write_params(queue);
+  }
+
+  fn write_params(&self, queue: &wgpu::Queue) {
+    queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&TonemapParams {
+      exposure: self.exposure,
+      curve: self.tonemap as u32,
+      encode_srgb: self.encode_srgb as u32,
+      _padding: 0,
+    }));
+  }
+
+  pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+    if (self.width, self.height) == (width, height) {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+    self.target = HdrTarget::new(device, width, height);
+    self.bind_group = Self::bind_group(device, &self.pipeline, &self.target, &self.sampler, &self.params);
+  }
+
+  // Tonemaps the HDR texture into `output`, which has to be a view in `ColorPolicy::render_format`.
+  // The pass is timed while `timer` is recording.
+  pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView, timer: Option<&GpuTimer>) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Tonemap Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: output,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      occlusion_query_set: None,
+      timestamp_writes: timer.and_then(|timer| timer.render_pass_writes("Tonemap Pass")),
+    });
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+impl RenderNode for HdrPipeline {
+  fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+    HdrPipeline::resize(self, &gpu.device, width, height);
+  }
+
+  // Tonemaps into the frame, whose format has to be the policy's `render_format`.
+  fn encode(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    HdrPipeline::encode(self, encoder, frame.view, frame.timer);
+  }
+}
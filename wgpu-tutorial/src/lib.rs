@@ -1,249 +1,128 @@
-use winit::{
-  dpi::PhysicalSize, event::*, event_loop::{ControlFlow, EventLoop}, window::{self, Window, WindowBuilder}
-};
+use renderer::{ClearNode, Frame, Gpu, RenderNode, RunOptions, Scene};
 
-use wgpu::{Surface, SurfaceTargetUnsafe};
+#[cfg(not(target_arch = "wasm32"))]
+mod compute;
+#[cfg(not(target_arch = "wasm32"))]
+pub use compute::{gpu_map, GpuMapError};
+
+pub use renderer::{ColorPolicy, ColorSpace, GpuError, SurfaceSupport};
+mod hdr;
+pub use hdr::{HdrPipeline, Tonemap};
 
 #[cfg(target_arch="wasm32")]
 use wasm_bindgen::prelude::*;
 
-struct State {
-  surface: wgpu::Surface<'static>,
-  device: wgpu::Device,
-  queue: wgpu::Queue,
-  config: wgpu::SurfaceConfiguration,
-  size: winit::dpi::PhysicalSize<u32>,
-  // The window must be declared after the surface so
-  // it gets dropped after it as the surface contains
-  // unsafe references to the window's resources.
-  window: Window,
+// What `run` lets the user pick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+  pub tonemap: Tonemap,
+  pub color_space: ColorSpace,
+  pub render_scale: f32,
+  pub present_mode: wgpu::PresentMode,
+  pub frame_latency: u32,
+  pub max_fps: Option<f64>,
 }
 
-impl State {
-    async fn new(window: Window) -> Self {
-      let size = window.inner_size();
-      // let size = PhysicalSize::new(100, 100);
-
-      // The instance is a handler to the GPU
-      // Backend::all => Vulkan + Metal + DX12 + Browser WebGPU
-      let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-        backends: wgpu::Backends::all(),
-        ..Default::default()
-      });
-
-      // # safety
-      cfg_if::cfg_if! {
-        if #[cfg(target_arch = "wasm32")] {
-          use winit::platform::web::WindowExtWebSys;
-          let canvas = window.canvas().unwrap();
-          let surface = instance.create_surface(wgpu::SurfaceTarget::Canvas(canvas)).unwrap();
-        } else {
-          let surface = unsafe { instance.create_surface_unsafe(SurfaceTargetUnsafe::from_window(&window).unwrap()) }.unwrap();
-        }
-      }
-
-      let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::default(),
-        compatible_surface: Some(&surface),
-        force_fallback_adapter: false,
-      }).await.unwrap();
-
-      let adapter_info = adapter.get_info();
-      let gpu_info = format!(
-        "using {}, backend {:?}。",
-        adapter_info.name, adapter_info.backend
-      );
-
-      #[cfg(not(target_arch = "wasm32"))]
-      println!("{gpu_info}");
-      #[cfg(target_arch = "wasm32")]
-      log::info!( "{gpu_info}" );
-
-      let (device, queue) = adapter.request_device(
-        &wgpu::DeviceDescriptor {
-          label: None,
-          required_features: wgpu::Features::empty(),
-          required_limits: {
-            cfg_if::cfg_if! {
-              if #[cfg(all(target_arch="wasm32", feature="webgl"))] {
-                wgpu::Limits::downlevel_webgl2_defaults()
-              } else {
-                wgpu::Limits::default()
-              }
-            }
-          },
-        },
-        None
-      ).await.unwrap();
-      
-      let surface_caps = surface.get_capabilities(&adapter);
-
-      // use sRGB if available
-      let prefered = surface_caps.formats[0];
-      let surface_format = if cfg!(all(target_arch = "wasm32", not(feature = "webgl"))) {
-        // Chrome WebGPU doesn't support sRGB:
-        // unsupported swap chain format "xxxx8unorm-srgb"
-          prefered.remove_srgb_suffix()
-      } else {
-          prefered
-      };
-
-      let view_formats = if cfg!(feature = "webgl") {
-        // panicked at 'Error in Surface::configure: Validation Error
-        // Caused by:
-        // Downlevel flags DownlevelFlags(SURFACE_VIEW_FORMATS) are required but not supported on the device.
-        vec![]
-      } else {
-        vec![surface_format.add_srgb_suffix(), surface_format.remove_srgb_suffix()]
-      };
-
-      let config = wgpu::SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface_format,
-        width: size.width,
-        height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
-        alpha_mode: surface_caps.alpha_modes[0],
-        view_formats: view_formats,
-        desired_maximum_frame_latency: 2,
-      };
-      surface.configure(&device, &config);
-
-      Self { surface, device, queue, config, size, window }
-    }
-
-    pub fn window(&self) -> &Window {
-      &self.window
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      tonemap: Tonemap::default(),
+      color_space: ColorSpace::default(),
+      render_scale: 1.0,
+      present_mode: wgpu::PresentMode::Fifo,
+      frame_latency: 2,
+      max_fps: None,
     }
+  }
+}
 
-    fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-      if new_size.width > 0 && new_size.height > 0 {
-        self.size = new_size;
-        #[cfg(target_arch="wasm32")] {
-          let size_info = format!("[resize]: w {} h {}", new_size.width, new_size.height);
-          log::info!("{size_info}");
+impl Options {
+  // `--tonemap reinhard|aces`, `--color-space srgb|linear`, `--render-scale <factor>`,
+  // `--present-mode <mode>`, `--frame-latency <frames>` and `--max-fps <fps>`, without the
+  // program name.
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+    let mut options = Self::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+      let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+      match arg.as_str() {
+        "--tonemap" => options.tonemap = value()?.parse()?,
+        "--color-space" => options.color_space = value()?.parse()?,
+        "--render-scale" => {
+          options.render_scale = value()?.parse().map_err(|e| format!("--render-scale: {e}"))?;
+        }
+        "--present-mode" => options.present_mode = renderer::parse_present_mode(&value()?)?,
+        "--frame-latency" => {
+          options.frame_latency = value()?.parse().map_err(|e| format!("--frame-latency: {e}"))?;
+        }
+        "--max-fps" => {
+          let fps = value()?.parse().map_err(|e| format!("--max-fps: {e}"))?;
+          if renderer::frame_interval(fps).is_none() {
+            return Err("--max-fps expects a positive rate".to_string());
+          }
+          options.max_fps = Some(fps);
         }
-        self.config.width = ((new_size.width as f64) / self.window().scale_factor()) as u32;
-        self.config.height = ((new_size.height as f64) / self.window().scale_factor()) as u32;
-        self.surface.configure(&self.device, &self.config)
+        _ => return Err(format!("unknown argument `{arg}`")),
       }
     }
+    Ok(options)
+  }
+}
 
-    #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
-      false
-    }
-
-    fn update(&mut self) { }
-
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-      let output = self.surface.get_current_texture()?;
-      let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-      let mut encoder = self.device.create_command_encoder(& wgpu::CommandEncoderDescriptor{
-        label: Some("Render Encoder"),
-      });
+// A device and queue with no window or surface behind them, on the software adapter if
+// there is one.
+pub async fn headless_device() -> Result<(wgpu::Device, wgpu::Queue), GpuError> {
+  let Gpu { device, queue, .. } = Gpu::headless(Gpu::default_limits()).await?;
+  Ok((device, queue))
+}
 
-      {
-        let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-          label: Some("First Render Pass"),
-          color_attachments: &[Some(wgpu::RenderPassColorAttachment{
-            view: &view,
-            resolve_target: None,
-            ops: wgpu::Operations { 
-              load: wgpu::LoadOp::Clear(wgpu::Color {
-                r: 0.1, g: 0.2, b: 0.3, a: 1.0
-              }), 
-              store: wgpu::StoreOp::Store
-            }
-          })],
-          depth_stencil_attachment: None,
-          occlusion_query_set: None,
-          timestamp_writes: None,
-        });
-      };
+// The clear color, drawn into the HDR target and tonemapped onto the surface.
+struct HdrScene {
+  clear: ClearNode,
+  hdr: HdrPipeline,
+}
 
-      self.queue.submit(std::iter::once(encoder.finish()));
-      output.present();
+impl Scene for HdrScene {
+  fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+    RenderNode::resize(&mut self.hdr, gpu, width, height);
+  }
 
-      Ok(())
-    }
+  fn render(&self, frame: &Frame, encoder: &mut wgpu::CommandEncoder) {
+    let hdr_frame = Frame { view: self.hdr.view(), format: HdrPipeline::FORMAT, ..*frame };
+    self.clear.encode(&hdr_frame, encoder);
+    RenderNode::encode(&self.hdr, frame, encoder);
+  }
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub async fn run() {
+  renderer::init_logger();
+
   cfg_if::cfg_if! {
     if #[cfg(target_arch="wasm32")] {
-      std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-      console_log::init_with_level(log::Level::Info).expect("Couldn't initialize logger");
+      let options = Options::default();
     } else {
-      env_logger::init();
+      let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|e| panic!("[options]: {e}"));
     }
   }
-  
-  let event_loop = EventLoop::new().unwrap();
 
-  let window = WindowBuilder::new().build(&event_loop).unwrap();
-  window.set_title("Rust GPU Programming");
-
-  // add canvas to the HTML document that we will host our application
-  #[cfg(target_arch="wasm32")] {
+  let run_options = RunOptions {
+    title: "Rust GPU Programming".to_string(),
     // Winit prevents sizing with CSS, so we have to set
     // the size manually when on web.
-    use winit::dpi::PhysicalSize;
-    // let _ = window.request_inner_size(PhysicalSize::new(100, 400));
-
-    use winit::platform::web::WindowExtWebSys;
-    web_sys::window()
-      .and_then(|win| win.document())
-      .and_then(|doc| {
-        let dst = doc.get_element_by_id("wgpu-container")?;
-        let canvas = window.canvas().unwrap();
-        canvas.set_width((100f32 * window.scale_factor() as f32) as u32);
-        canvas.set_height((100f32 * window.scale_factor() as f32) as u32);
-        let el = web_sys::HtmlCanvasElement::from(canvas);
-        dst.append_child(&el).ok()?;
-        Some(())
-      })
-      .expect("couldn't add canvas to document");
-  }
-
-  let mut state = State::new(window).await;
-
-  event_loop.set_control_flow(ControlFlow::Wait);
-  
-  let _ = event_loop.run(move |event, control_flow| {
-    match event {
-      Event::WindowEvent { 
-        ref event, 
-        window_id 
-      } if window_id == state.window().id() =>  if !state.input(event) {
-        match event {
-          WindowEvent::CloseRequested => control_flow.exit(),
-          WindowEvent::Resized(new_size) => state.resize(*new_size),
-          WindowEvent::RedrawRequested => {
-            state.update();
-            match state.render() {
-              Ok(_) => {},
-              // Reconfigure the surface is lost
-              Err(wgpu::SurfaceError::Lost) => eprintln!("Surface is lost"),
-              // The system is out of memory
-              Err(wgpu::SurfaceError::OutOfMemory) => control_flow.exit(),
-              //  others
-              Err(e) => eprintln!("{:?}", e),
-            }
-          },
-          _ => {}
-        }
-      },
-
-      Event::AboutToWait => {
-        // RedrawRequested will only trigger once unless we manually request it.
-        state.window().request_redraw();
-      },
-
-      _ => (),
+    size: cfg!(target_arch = "wasm32").then(|| winit::dpi::LogicalSize::new(100, 100).into()),
+    color_space: options.color_space,
+    render_scale: options.render_scale,
+    present_mode: options.present_mode,
+    frame_latency: options.frame_latency,
+    target_fps: options.max_fps,
+    ..Default::default()
+  };
+  renderer::run(run_options, move |renderer| {
+    let (width, height) = renderer.size();
+    HdrScene {
+      clear: ClearNode::new(wgpu::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 }),
+      hdr: HdrPipeline::new(&renderer.gpu().device, width, height, renderer.policy(), options.tonemap),
     }
-  });
-
-}
\ No newline at end of file
+  }).await;
+}
@@ -1,5 +1,5 @@
-use wgpu_tutorial::run;
-
 fn main() {
-    run();
-}
\ No newline at end of file
+    // on the web `run` is started by wasm-bindgen instead
+    #[cfg(not(target_arch = "wasm32"))]
+    pollster::block_on(wgpu_tutorial::run());
+}
@@ -0,0 +1,56 @@
+// 把 HDR 纹理映射到 [0, 1] 并写入表面
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) uv: vec2f,
+};
+
+// 一个覆盖全屏的三角形
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.uv = uv;
+  out.clip_position = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+  return out;
+}
+
+struct TonemapParams {
+    exposure: f32,
+    // 0: Reinhard, 1: ACES
+    curve: u32,
+    // 表面不是 sRGB 格式时在这里编码
+    encode_srgb: u32,
+};
+
+@group(0) @binding(0)
+var hdr: texture_2d<f32>;
+@group(0) @binding(1)
+var hdr_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> params: TonemapParams;
+
+fn reinhard(x: vec3f) -> vec3f {
+  return x / (1.0 + x);
+}
+
+// Narkowicz 的 ACES 近似
+fn aces(x: vec3f) -> vec3f {
+  return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3f(0.0), vec3f(1.0));
+}
+
+fn srgb_encode(x: vec3f) -> vec3f {
+  let low = x * 12.92;
+  let high = 1.055 * pow(x, vec3f(1.0 / 2.4)) - 0.055;
+  return select(high, low, x <= vec3f(0.0031308));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let color = textureSample(hdr, hdr_sampler, in.uv);
+  let exposed = max(color.rgb * params.exposure, vec3f(0.0));
+  var mapped = select(reinhard(exposed), aces(exposed), params.curve == 1u);
+  if (params.encode_srgb == 1u) {
+    mapped = srgb_encode(mapped);
+  }
+  return vec4f(mapped, 1.0);
+}
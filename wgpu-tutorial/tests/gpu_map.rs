@@ -0,0 +1,75 @@
+use std::sync::OnceLock;
+
+use wgpu_tutorial::{gpu_map, headless_device, GpuMapError};
+
+// one device for all tests, GL drivers don't like several at once
+fn gpu() -> &'static (wgpu::Device, wgpu::Queue) {
+  static GPU: OnceLock<(wgpu::Device, wgpu::Queue)> = OnceLock::new();
+  GPU.get_or_init(|| pollster::block_on(headless_device()).expect("no adapter to run the tests on"))
+}
+
+const SQUARE: &str = "
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3u) {
+  if (id.x >= arrayLength(&input)) {
+    return;
+  }
+  output[id.x] = input[id.x] * input[id.x];
+}
+";
+
+#[test]
+fn squares_every_element() {
+  let (device, queue) = gpu();
+  // not a multiple of the workgroup size
+  let input: Vec<f32> = (0..1000).map(|i| i as f32 * 0.5).collect();
+
+  let output: Vec<f32> = gpu_map(device, queue, SQUARE, "main", &[&input]).unwrap();
+  assert_eq!(output, input.iter().map(|x| x * x).collect::<Vec<_>>());
+}
+
+#[test]
+fn inputs_follow_binding_order() {
+  let (device, queue) = gpu();
+  let src = "
+@group(1) @binding(0) var<storage, read_write> output: array<i32>;
+@group(0) @binding(3) var<storage, read> b: array<u32>;
+@group(0) @binding(1) var<storage, read> a: array<u32>;
+
+@compute @workgroup_size(32)
+fn subtract(@builtin(global_invocation_id) id: vec3u) {
+  if (id.x < arrayLength(&output)) {
+    output[id.x] = i32(a[id.x]) - i32(b[id.x]);
+  }
+}
+";
+  let a: Vec<u32> = (0..100).collect();
+  let b: Vec<u32> = (0..100).map(|i| i * 2).collect();
+
+  let output: Vec<i32> = gpu_map(device, queue, src, "subtract", &[&a, &b]).unwrap();
+  assert_eq!(output, (0..100).map(|i| -i).collect::<Vec<_>>());
+}
+
+#[test]
+fn mismatches_are_reported_before_dispatch() {
+  let (device, queue) = gpu();
+  let input = [1.0f32; 4];
+
+  let result = gpu_map::<f32, f32>(device, queue, SQUARE, "missing", &[&input]);
+  assert!(matches!(result, Err(GpuMapError::MissingEntryPoint(name)) if name == "missing"));
+
+  let result = gpu_map::<f32, f32>(device, queue, SQUARE, "main", &[&input, &input]);
+  assert!(matches!(result, Err(GpuMapError::InputCount { expected: 1, got: 2 })));
+
+  let result = gpu_map::<f32, [f32; 2]>(device, queue, SQUARE, "main", &[&input]);
+  assert!(matches!(result, Err(GpuMapError::Stride { group: 0, binding: 1, shader: 4, host: 8 })));
+
+  let result = gpu_map::<f32, f32>(device, queue, "fn main() {", "main", &[&input]);
+  assert!(matches!(result, Err(GpuMapError::Parse(_))));
+
+  let empty = gpu_map::<f32, f32>(device, queue, SQUARE, "main", &[&[]]).unwrap();
+  assert!(empty.is_empty());
+}
@@ -0,0 +1,156 @@
+use std::sync::OnceLock;
+
+use wgpu::TextureFormat;
+use wgpu_tutorial::{headless_device, ColorPolicy, ColorSpace, HdrPipeline, Options, Tonemap};
+
+// one device for all tests, GL drivers don't like several at once
+fn gpu() -> &'static (wgpu::Device, wgpu::Queue) {
+  static GPU: OnceLock<(wgpu::Device, wgpu::Queue)> = OnceLock::new();
+  GPU.get_or_init(|| pollster::block_on(headless_device()).expect("no adapter to run the tests on"))
+}
+
+#[test]
+fn options_parse_args() {
+  let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+  assert_eq!(Options::parse(args("")), Ok(Options::default()));
+  assert_eq!(
+    Options::parse(args("--tonemap Reinhard --color-space linear")),
+    Ok(Options { tonemap: Tonemap::Reinhard, color_space: ColorSpace::Linear, ..Options::default() }),
+  );
+  assert!(Options::parse(args("--tonemap filmic")).is_err());
+  assert!(Options::parse(args("--tonemap")).is_err());
+  assert!(Options::parse(args("--exposure 2")).is_err());
+  assert_eq!(Options::parse(args("--render-scale 0.5")).map(|o| o.render_scale), Ok(0.5));
+  assert!(Options::parse(args("--render-scale half")).is_err());
+  assert_eq!(
+    Options::parse(args("--present-mode mailbox --frame-latency 1 --max-fps 30")),
+    Ok(Options {
+      present_mode: wgpu::PresentMode::Mailbox,
+      frame_latency: 1,
+      max_fps: Some(30.0),
+      ..Options::default()
+    }),
+  );
+  assert!(Options::parse(args("--present-mode vsync")).is_err());
+  for fps in ["0", "-30", "inf", "1e-30"] {
+    assert!(Options::parse(args(&format!("--max-fps {fps}"))).is_err(), "{fps}");
+  }
+}
+
+fn reinhard(x: f32) -> f32 {
+  x / (1.0 + x)
+}
+
+fn aces(x: f32) -> f32 {
+  ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+}
+
+fn srgb_encode(x: f32) -> f32 {
+  if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 }
+}
+
+// Clears the HDR target to `color`, tonemaps it into a `format` texture and reads one pixel back.
+fn tonemap_pixel(
+  hdr: &mut HdrPipeline,
+  format: TextureFormat,
+  color: wgpu::Color,
+  tonemap: Tonemap,
+  exposure: f32,
+) -> [u8; 4] {
+  let (device, queue) = gpu();
+  let (width, height) = (64, 4);
+  hdr.set_tonemap(queue, tonemap);
+  hdr.set_exposure(queue, exposure);
+
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Output Texture"),
+    size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    view_formats: &[],
+  });
+  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+  // 64 pixels of 4 bytes is one aligned row
+  let readback = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Readback Buffer"),
+    size: (width * height * 4) as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+
+  let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+  encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+    label: Some("Clear HDR Pass"),
+    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+      view: hdr.view(),
+      resolve_target: None,
+      ops: wgpu::Operations { load: wgpu::LoadOp::Clear(color), store: wgpu::StoreOp::Store },
+    })],
+    depth_stencil_attachment: None,
+    occlusion_query_set: None,
+    timestamp_writes: None,
+  });
+  hdr.encode(&mut encoder, &view, None);
+  encoder.copy_texture_to_buffer(
+    texture.as_image_copy(),
+    wgpu::ImageCopyBuffer {
+      buffer: &readback,
+      layout: wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(width * 4), rows_per_image: None },
+    },
+    wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+  );
+  queue.submit(std::iter::once(encoder.finish()));
+
+  let slice = readback.slice(..);
+  slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+  device.poll(wgpu::Maintain::Wait);
+  let pixel = slice.get_mapped_range()[..4].try_into().unwrap();
+  readback.unmap();
+  pixel
+}
+
+fn assert_close(pixel: [u8; 4], expected: [f32; 3]) {
+  for (channel, expected) in pixel.iter().zip(expected) {
+    let expected = (expected * 255.0).round() as i32;
+    assert!((*channel as i32 - expected).abs() <= 2, "{pixel:?} vs {expected}");
+  }
+  assert_eq!(pixel[3], 255);
+}
+
+#[test]
+fn tonemaps_hdr_values_into_the_surface_format() {
+  let (device, _) = gpu();
+  let color = wgpu::Color { r: 2.0, g: 0.5, b: 0.1, a: 1.0 };
+  let hdr_color = [2.0f32, 0.5, 0.1];
+
+  for format in [TextureFormat::Rgba8UnormSrgb, TextureFormat::Rgba8Unorm] {
+    // either the format or the shader encodes sRGB, the bytes are the same
+    let policy = ColorPolicy::for_format(format, ColorSpace::Srgb);
+    assert_eq!(policy.encode_srgb, !format.is_srgb());
+    let mut hdr = HdrPipeline::new(device, 64, 4, &policy, Tonemap::default());
+
+    for (tonemap, curve) in [(Tonemap::Reinhard, reinhard as fn(f32) -> f32), (Tonemap::Aces, aces)] {
+      let pixel = tonemap_pixel(&mut hdr, format, color, tonemap, 1.0);
+      assert_close(pixel, hdr_color.map(|x| srgb_encode(curve(x))));
+
+      let pixel = tonemap_pixel(&mut hdr, format, color, tonemap, 0.5);
+      assert_close(pixel, hdr_color.map(|x| srgb_encode(curve(x * 0.5))));
+    }
+  }
+
+  // values past 1.0 are compressed instead of clipped
+  let policy = ColorPolicy::for_format(TextureFormat::Rgba8Unorm, ColorSpace::Linear);
+  let mut hdr = HdrPipeline::new(device, 64, 4, &policy, Tonemap::Reinhard);
+  let bright = wgpu::Color { r: 3.0, g: 1.0, b: 0.0, a: 1.0 };
+  let pixel = tonemap_pixel(&mut hdr, TextureFormat::Rgba8Unorm, bright, Tonemap::Reinhard, 1.0);
+  assert_close(pixel, [0.75, 0.5, 0.0]);
+
+  // the bind group follows the new texture
+  hdr.resize(device, 128, 8);
+  let pixel = tonemap_pixel(&mut hdr, TextureFormat::Rgba8Unorm, bright, Tonemap::Reinhard, 1.0);
+  assert_close(pixel, [0.75, 0.5, 0.0]);
+}